@@ -3,20 +3,27 @@ if [[ $# -ne 1 ]]; then
     exit 1
 fi
 
-filename_src=$(printf "./src/bin/day%02d.rs" $1)
+day=$((10#$1))
+filename_src=$(printf "./src/day%02d.rs" $1)
+filename_bin=$(printf "./src/bin/day%02d.rs" $1)
 filename_data=$(printf "./data/day%02d.txt" $1)
 
 cat << RUST > $filename_src
+use crate::solver::{Answer, Parameters};
+
+pub fn solve(_input: &str, _params: &Parameters) -> Vec<Answer> {
+    Vec::new()
+}
+RUST
+
+cat << RUST > $filename_bin
 use std::io;
-use std::io::prelude::*;
 
 fn main() -> io::Result<()> {
-    let mut input = String::new();
-    io::stdin().lock().read_to_string(&mut input).unwrap();
-    let input = &input[..];
-
-    Ok(())
+    adventofcode_rust::solver::main($day)
 }
 RUST
 
 touch $filename_data
+
+echo "Now add the module to src/lib.rs and the solver to src/solver.rs"
@@ -1,8 +1,4 @@
 #!/bin/bash
 
-for i in $(seq -w 26); do
-    if [ -x "target/release/day${i}" ]; then
-        echo "------------------------------------------------------------------------- DAY ${i}"
-        "target/release/day${i}" < "data/day${i}.txt"
-    fi
-done
+# Pass --no-cache to recompute every answer instead of using the answer cache.
+cargo build --release --quiet && target/release/aoc run "$@"
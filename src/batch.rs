@@ -0,0 +1,150 @@
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::solver::Answer;
+use crate::tsv::unescape;
+
+pub struct BatchInput {
+    pub label: String,
+    pub path: PathBuf,
+}
+
+pub enum Outcome {
+    Solved {
+        answers: Vec<Answer>,
+        elapsed: Duration,
+    },
+    Failed(String),
+    TimedOut,
+}
+
+/// Finds the inputs for a day in `dir`: `NAME/dayNN.txt` for every
+/// subdirectory, laid out like `data/`, and every file directly in `dir` whose
+/// name starts with `dayNN`. If `any_file` is set, every file directly in
+/// `dir` is used regardless of its name.
+pub fn find_inputs(dir: &Path, day: u32, any_file: bool) -> io::Result<Vec<BatchInput>> {
+    let file_name = format!("day{:02}.txt", day);
+    let prefix = format!("day{:02}", day);
+    let mut inputs = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if path.is_dir() {
+            let path = path.join(&file_name);
+            if path.is_file() {
+                inputs.push(BatchInput { label: name, path });
+            }
+        } else if any_file || name.starts_with(&prefix) {
+            inputs.push(BatchInput { label: name, path });
+        }
+    }
+
+    inputs.sort_by(|a, b| a.label.cmp(&b.label));
+    Ok(inputs)
+}
+
+/// Extracts the panic message from the stderr of a failed solver.
+fn failure_message(stderr: &str) -> String {
+    let mut lines = stderr.lines();
+    while let Some(line) = lines.next() {
+        if line.contains("panicked at") {
+            if let Some(message) = lines.next() {
+                return message.to_string();
+            }
+        }
+    }
+    match stderr.lines().next() {
+        Some(line) => line.to_string(),
+        None => String::from("solver exited with an error"),
+    }
+}
+
+/// Reads everything from `pipe` on a thread of its own.
+fn drain<R: Read + Send + 'static>(mut pipe: R) -> thread::JoinHandle<io::Result<String>> {
+    thread::spawn(move || {
+        let mut contents = String::new();
+        pipe.read_to_string(&mut contents)?;
+        Ok(contents)
+    })
+}
+
+/// Runs `aoc solve --tsv DAY NAME=VALUE...` in a child process with the input
+/// on stdin, so that a solver that panics or runs past the budget can be
+/// killed without affecting the other runs.
+pub fn run_isolated(
+    exe: &Path,
+    day: u32,
+    params: &[(String, String)],
+    input: Vec<u8>,
+    budget: Duration,
+) -> io::Result<Outcome> {
+    let mut child = Command::new(exe)
+        .args(["solve", "--tsv", &day.to_string()])
+        .args(
+            params
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // A solver that fails before reading all of its input closes the pipe,
+    // which only matters to the writer.
+    let mut stdin = child.stdin.take().unwrap();
+    thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+
+    // The pipes are drained while the child runs, so that one that fills
+    // them up does not block until it is killed.
+    let stdout = drain(child.stdout.take().unwrap());
+    let stderr = drain(child.stderr.take().unwrap());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > budget {
+            child.kill()?;
+            child.wait()?;
+            return Ok(Outcome::TimedOut);
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    let stdout = stdout.join().unwrap()?;
+    let stderr = stderr.join().unwrap()?;
+
+    if !status.success() {
+        return Ok(Outcome::Failed(failure_message(&stderr)));
+    }
+
+    let mut answers = Vec::new();
+    let mut elapsed = start.elapsed();
+    for line in stdout.lines() {
+        let fields: Vec<&str> = line.splitn(3, '\t').collect();
+        match fields.as_slice() {
+            ["elapsed", micros] => {
+                if let Ok(micros) = micros.parse() {
+                    elapsed = Duration::from_micros(micros);
+                }
+            }
+            ["answer", description, value] => {
+                answers.push(Answer::new(&unescape(description), unescape(value)))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Outcome::Solved { answers, elapsed })
+}
@@ -0,0 +1,382 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
+
+use adventofcode_rust::batch::{self, Outcome};
+use adventofcode_rust::cache::{self, Cache};
+use adventofcode_rust::report;
+use adventofcode_rust::runner;
+use adventofcode_rust::server;
+use adventofcode_rust::solver::{self, Solver};
+use adventofcode_rust::tsv;
+
+const USAGE: &str = "Usage: aoc run [--no-cache] [DAY...]
+       aoc solve [--tsv] DAY [NAME=VALUE...]
+       aoc batch [--budget SECONDS] DIR [DAY...]
+       aoc report [--cache] [--output DIR] [--visualizations DIR]
+       aoc profile [DAY...]
+       aoc serve [--address ADDRESS] [--timeout SECONDS]
+       aoc cache list
+       aoc cache show DAY
+       aoc cache clear [DAY...]";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
+
+fn parse_days(args: &[String]) -> Vec<&'static Solver> {
+    if args.is_empty() {
+        return solver::solvers().iter().collect();
+    }
+    args.iter()
+        .map(|a| match a.parse().ok().and_then(solver::find) {
+            Some(s) => s,
+            None => {
+                eprintln!("No solver for day {}", a);
+                process::exit(1);
+            }
+        })
+        .collect()
+}
+
+fn run(args: &[String]) -> io::Result<()> {
+    let use_cache = args.first().map(|a| a.as_str()) != Some("--no-cache");
+    let args = if use_cache { args } else { &args[1..] };
+    let mut cache = Cache::open(Path::new(cache::DEFAULT_PATH))?;
+
+    for solver in parse_days(args) {
+        let input = fs::read_to_string(solver.input_path())?;
+        let params = solver.default_parameters();
+
+        let result = if use_cache {
+            let result = runner::run(solver, &input, &params, Some(&mut cache));
+            cache.save()?;
+            result
+        } else {
+            runner::run(solver, &input, &params, None)
+        };
+
+        println!(
+            "------------------------------------------------------------------------- DAY {:02}{}",
+            solver.day,
+            if result.elapsed.is_none() {
+                " (cached)"
+            } else {
+                ""
+            }
+        );
+        for answer in result.answers {
+            println!("{}: {}", answer.description, answer.value);
+        }
+    }
+    Ok(())
+}
+
+/// Solves the input on stdin, optionally overriding parameters. The `--tsv`
+/// output is what `batch` reads back from its child processes.
+fn solve(args: &[String]) -> io::Result<()> {
+    let tsv_output = args.first().map(|a| a.as_str()) == Some("--tsv");
+    let args = if tsv_output { &args[1..] } else { args };
+    if args.is_empty() {
+        usage();
+    }
+    let solver = parse_days(&args[..1])[0];
+
+    let mut params = solver.default_parameters();
+    for arg in args[1..].iter() {
+        let (name, value) = match arg.find('=') {
+            Some(i) => (&arg[..i], &arg[i + 1..]),
+            None => usage(),
+        };
+        if let Err(e) = params.set(name, value) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+
+    let start = Instant::now();
+    let answers = (solver.solve)(&input, &params);
+    let elapsed = start.elapsed();
+
+    for answer in answers {
+        if tsv_output {
+            println!(
+                "answer\t{}\t{}",
+                tsv::escape(&answer.description),
+                tsv::escape(&answer.value)
+            );
+        } else {
+            println!("{}: {}", answer.description, answer.value);
+        }
+    }
+    if tsv_output {
+        println!("elapsed\t{}", elapsed.as_micros());
+    }
+    Ok(())
+}
+
+fn batch(args: &[String]) -> io::Result<()> {
+    let mut budget = Duration::from_secs(10);
+    let mut args = args;
+    if args.first().map(|a| a.as_str()) == Some("--budget") {
+        // Zero, negative, infinite and NaN budgets make no sense.
+        let seconds = args.get(1).and_then(|s| s.parse::<f64>().ok());
+        match seconds
+            .filter(|&s| s > 0.0)
+            .and_then(|s| Duration::try_from_secs_f64(s).ok())
+        {
+            Some(seconds) => budget = seconds,
+            None => usage(),
+        }
+        args = &args[2..];
+    }
+    if args.is_empty() {
+        usage();
+    }
+    let dir = Path::new(&args[0]);
+    let solvers = parse_days(&args[1..]);
+    let any_file = solvers.len() == 1;
+    let exe = env::current_exe()?;
+
+    let mut problems = Vec::new();
+    let mut run_count = 0;
+    for solver in solvers {
+        let inputs = batch::find_inputs(dir, solver.day, any_file)?;
+        if inputs.is_empty() {
+            continue;
+        }
+
+        let mut rows = Vec::new();
+        for input in inputs.iter() {
+            run_count += 1;
+            let mut row = vec![input.label.clone()];
+            let contents = fs::read(&input.path)?;
+            match batch::run_isolated(&exe, solver.day, &[], contents, budget)? {
+                Outcome::Solved { answers, elapsed } => {
+                    row.push(report::format_duration(elapsed));
+                    row.extend(answers.into_iter().map(|a| a.value));
+                }
+                Outcome::Failed(message) => {
+                    row.push(String::from("FAILED"));
+                    row.push(message.clone());
+                    problems.push(format!(
+                        "day {:02} {}: {}",
+                        solver.day, input.label, message
+                    ));
+                }
+                Outcome::TimedOut => {
+                    let message =
+                        format!("exceeded the budget of {}", report::format_duration(budget));
+                    row.push(String::from("TIMEOUT"));
+                    row.push(message.clone());
+                    problems.push(format!(
+                        "day {:02} {}: {}",
+                        solver.day, input.label, message
+                    ));
+                }
+            }
+            rows.push(row);
+        }
+
+        let parts = rows.iter().map(|r| r.len() - 2).max().unwrap_or(0);
+        let mut header = vec![String::from("Input"), String::from("Time")];
+        header.extend((1..=parts).map(|p| format!("Part {}", p)));
+        let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+        for row in rows.iter() {
+            for (i, cell) in row.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(cell.len());
+            }
+        }
+
+        println!("Day {:02}: {}", solver.day, solver.title);
+        for row in std::iter::once(&header).chain(rows.iter()) {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| match widths.get(i) {
+                    Some(w) if i + 1 < row.len() => format!("{:w$}", cell, w = w),
+                    _ => cell.clone(),
+                })
+                .collect();
+            println!("  {}", cells.join("  "));
+        }
+        println!();
+    }
+
+    if problems.is_empty() {
+        println!("All {} runs finished within the budget", run_count);
+        Ok(())
+    } else {
+        println!(
+            "{} of {} runs failed or exceeded the budget:",
+            problems.len(),
+            run_count
+        );
+        for p in problems.iter() {
+            println!("  {}", p);
+        }
+        process::exit(1);
+    }
+}
+
+fn report(args: &[String]) -> io::Result<()> {
+    // Answers from the cache have no timings, so the report only uses it when
+    // asked to.
+    let mut use_cache = false;
+    let mut output_dir = String::from("report");
+    let mut visualization_dir = String::from("visualizations");
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cache" => use_cache = true,
+            "--output" => output_dir = args.next().unwrap_or_else(|| usage()).clone(),
+            "--visualizations" => {
+                visualization_dir = args.next().unwrap_or_else(|| usage()).clone()
+            }
+            _ => usage(),
+        }
+    }
+
+    let mut cache = Cache::open(Path::new(cache::DEFAULT_PATH))?;
+    let mut results = Vec::new();
+    for solver in solver::solvers() {
+        let input = fs::read_to_string(solver.input_path())?;
+        let params = solver.default_parameters();
+        let cache = if use_cache { Some(&mut cache) } else { None };
+        results.push(runner::run(solver, &input, &params, cache));
+    }
+    if use_cache {
+        cache.save()?;
+    }
+
+    report::write(
+        &results,
+        Path::new(&output_dir),
+        Path::new(&visualization_dir),
+    )?;
+    println!("Report written to {}", output_dir);
+    Ok(())
+}
+
+fn cache_command(args: &[String]) -> io::Result<()> {
+    let mut cache = Cache::open(Path::new(cache::DEFAULT_PATH))?;
+    match args.first().map(|s| s.as_str()) {
+        Some("list") if args.len() == 1 => {
+            for e in cache.entries() {
+                println!(
+                    "day {:02} part {} v{} input {} params {}: {}",
+                    e.day, e.part, e.version, e.input_hash, e.parameters, e.value
+                );
+            }
+        }
+        Some("show") if args.len() == 2 => {
+            let day = parse_days(&args[1..])[0].day;
+            for e in cache.entries().iter().filter(|e| e.day == day) {
+                println!("Day:         {}", e.day);
+                println!("Part:        {}", e.part);
+                println!("Version:     {}", e.version);
+                println!("Input hash:  {}", e.input_hash);
+                println!("Parameters:  {}", e.parameters);
+                println!("Description: {}", e.description);
+                println!("Value:       {}", e.value);
+                println!();
+            }
+        }
+        Some("clear") => {
+            let days: Vec<u32> = if args.len() == 1 {
+                Vec::new()
+            } else {
+                parse_days(&args[1..]).iter().map(|s| s.day).collect()
+            };
+            let count = cache.clear(&days);
+            cache.save()?;
+            println!("Removed {} cache entries", count);
+        }
+        _ => usage(),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "profile")]
+fn profile(args: &[String]) -> io::Result<()> {
+    use adventofcode_rust::profile;
+
+    println!(
+        "{:>3} {:>4} {:>12} {:>16} {:>12}",
+        "Day", "Part", "Allocations", "Bytes allocated", "Peak heap"
+    );
+    for solver in parse_days(args) {
+        let input = fs::read_to_string(solver.input_path())?;
+        let params = solver.default_parameters();
+
+        profile::begin();
+        let answers = (solver.solve)(&input, &params);
+        let stats = profile::finish();
+        drop(answers);
+
+        for (i, s) in stats.iter().enumerate() {
+            let part = if stats.len() == 1 {
+                String::from("all")
+            } else {
+                (i + 1).to_string()
+            };
+            println!(
+                "{:>3} {:>4} {:>12} {:>16} {:>12}",
+                solver.day, part, s.allocations, s.bytes_allocated, s.peak_heap
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "profile"))]
+fn profile(_args: &[String]) -> io::Result<()> {
+    eprintln!("Profiling requires building with `--features profile`");
+    process::exit(1);
+}
+
+fn serve(args: &[String]) -> io::Result<()> {
+    let mut config = server::Config {
+        address: String::from("127.0.0.1:2017"),
+        timeout: Duration::from_secs(30),
+        exe: env::current_exe()?,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--address", Some(address)) => config.address = address.clone(),
+            // A zero timeout would time out every request.
+            ("--timeout", Some(seconds)) => match seconds.parse().ok().filter(|&s| s > 0) {
+                Some(s) => config.timeout = Duration::from_secs(s),
+                None => usage(),
+            },
+            _ => usage(),
+        }
+    }
+
+    server::serve(&config)
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("run") => run(&args[1..]),
+        Some("solve") => solve(&args[1..]),
+        Some("batch") => batch(&args[1..]),
+        Some("report") => report(&args[1..]),
+        Some("profile") => profile(&args[1..]),
+        Some("serve") => serve(&args[1..]),
+        Some("cache") => cache_command(&args[1..]),
+        _ => usage(),
+    }
+}
@@ -1,152 +1,101 @@
+use std::env;
+use std::fs;
 use std::io;
-use std::io::prelude::*;
-
-use std::collections::HashMap;
-
-use nom::{
-    branch::alt,
-    character::complete::char,
-    combinator::value,
-    multi::{many1, separated_list},
-    IResult,
-};
-
-enum Direction {
-    North,
-    East,
-    South,
-    West,
+use std::path::PathBuf;
+use std::process;
+
+use adventofcode_rust::day22::resume::{self, Options};
+
+const USAGE: &str = "Usage: day22 < INPUT
+       day22 bench FILE BURSTS
+       day22 run FILE BURSTS [OPTIONS]
+       day22 check-resume FILE BURSTS [OPTIONS]
+
+Options:
+       --rule TURNS            turn for every node state, each optionally followed
+                               by the state it leads to (default LNRU)
+       --infected STATE        node state of infected nodes (default 2)
+       --direction N|E|S|W     direction the carrier starts facing (default N)
+       --checkpoint-every N    save a checkpoint every N bursts
+       --checkpoint PATH       where to save checkpoints (default day22.checkpoint)
+       --resume PATH           resume from a checkpoint";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
 }
-use Direction::*;
 
-impl Direction {
-    fn turn_right(&self) -> Direction {
-        match self {
-            North => East,
-            East => South,
-            South => West,
-            West => North,
-        }
-    }
-    fn turn_left(&self) -> Direction {
-        match self {
-            North => West,
-            East => North,
-            South => East,
-            West => South,
-        }
-    }
-    fn reverse(&self) -> Direction {
-        match self {
-            North => South,
-            East => West,
-            South => North,
-            West => East,
-        }
-    }
-    fn move_forward(&self, coords: &(i32, i32)) -> (i32, i32) {
-        match self {
-            North => (coords.0, coords.1 - 1),
-            East => (coords.0 + 1, coords.1),
-            South => (coords.0, coords.1 + 1),
-            West => (coords.0 - 1, coords.1),
+/// Prints the output of a command on `path`, or its error.
+fn run(path: &str, command: impl Fn(&str) -> Result<String, String>) -> io::Result<()> {
+    let input = fs::read_to_string(path)?;
+    match command(&input) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
         }
     }
+    Ok(())
 }
 
-#[derive(Clone, Copy)]
-enum CellState {
-    Clean,
-    Infected,
-    Weakened,
-    Flagged,
-}
-
-fn parse_cell_state(input: &str) -> IResult<&str, CellState> {
-    alt((
-        value(CellState::Infected, char('#')),
-        value(CellState::Clean, char('.')),
-    ))(input)
-}
-
-fn parse_input(input: &str) -> IResult<&str, Vec<Vec<CellState>>> {
-    separated_list(char('\n'), many1(parse_cell_state))(input)
-}
-
-fn prepare_nodes(input: &Vec<Vec<CellState>>) -> HashMap<(i32, i32), CellState> {
-    let mut nodes: HashMap<(i32, i32), CellState> = HashMap::new();
-    for (y, v) in input.iter().enumerate() {
-        for (x, c) in v.iter().enumerate() {
-            if let CellState::Infected = c {
-                nodes.insert((x as i32, y as i32), CellState::Infected);
+fn parse_options(mut args: &[&str]) -> Options {
+    let mut options = Options::default();
+    loop {
+        match args {
+            [] => return options,
+            ["--rule", rule, rest @ ..] => {
+                options.rule = rule.to_string();
+                args = rest;
             }
-        }
-    }
-    nodes
-}
-
-fn iterate(num_bursts: u64, input: &Vec<Vec<CellState>>, part2: bool) -> u64 {
-    let current_coords = input[0].len() as i32 / 2;
-    let mut current_coords = (current_coords, current_coords);
-    let mut current_dir = North;
-
-    let mut nodes = prepare_nodes(&input);
-
-    let mut infections_count = 0;
-
-    let clean_transition_state = if part2 {
-        CellState::Weakened
-    } else {
-        CellState::Infected
-    };
-    let infected_transition_state = if part2 {
-        CellState::Flagged
-    } else {
-        CellState::Clean
-    };
-
-    for _iteration in 0..num_bursts {
-        match nodes.entry(current_coords).or_insert(CellState::Clean) {
-            CellState::Clean => {
-                current_dir = current_dir.turn_left();
-                nodes.insert(current_coords, clean_transition_state);
-                if !part2 {
-                    infections_count += 1;
-                }
+            ["--infected", infected, rest @ ..] => {
+                options.infected = infected.parse().unwrap_or_else(|_| usage());
+                args = rest;
             }
-            CellState::Infected => {
-                current_dir = current_dir.turn_right();
-                nodes.insert(current_coords, infected_transition_state);
+            ["--direction", direction, rest @ ..] => {
+                options.direction = direction.to_string();
+                args = rest;
+            }
+            ["--checkpoint-every", every, rest @ ..] => {
+                match every.parse() {
+                    Ok(every) if every > 0 => options.schedule.every = Some(every),
+                    _ => usage(),
+                }
+                args = rest;
             }
-            CellState::Weakened => {
-                nodes.insert(current_coords, CellState::Infected);
-                infections_count += 1;
+            ["--checkpoint", path, rest @ ..] => {
+                options.schedule.path = PathBuf::from(path);
+                args = rest;
             }
-            CellState::Flagged => {
-                current_dir = current_dir.reverse();
-                nodes.insert(current_coords, CellState::Clean);
+            ["--resume", path, rest @ ..] => {
+                options.schedule.resume = Some(PathBuf::from(path));
+                args = rest;
             }
+            _ => usage(),
         }
-        current_coords = current_dir.move_forward(&current_coords);
     }
-    infections_count
 }
 
 fn main() -> io::Result<()> {
-    let mut input = String::new();
-    io::stdin().lock().read_to_string(&mut input).unwrap();
-    let input = &input[..];
-
-    let (_rest, input) = parse_input(input).unwrap();
-
-    println!(
-        "Number of infections after 10000 iterations: {}",
-        iterate(10000, &input, false)
-    );
-    println!(
-        "Number of infections after 10000000 iterations: {}",
-        iterate(10_000_000, &input, true)
-    );
-
-    Ok(())
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => adventofcode_rust::solver::main(22),
+        ["bench", path, bursts] => {
+            let bursts = bursts.parse().unwrap_or_else(|_| usage());
+            run(path, |input| {
+                adventofcode_rust::day22::benchmark(input, bursts)
+            })
+        }
+        ["run", path, bursts, options @ ..] => {
+            let bursts = bursts.parse().unwrap_or_else(|_| usage());
+            let options = parse_options(options);
+            run(path, |input| resume::run(input, bursts, &options))
+        }
+        ["check-resume", path, bursts, options @ ..] => {
+            let bursts = bursts.parse().unwrap_or_else(|_| usage());
+            let options = parse_options(options);
+            run(path, |input| resume::check(input, bursts, &options))
+        }
+        _ => usage(),
+    }
 }
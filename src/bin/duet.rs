@@ -0,0 +1,365 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{self, Command};
+use std::time::{Duration, Instant};
+
+use adventofcode_rust::bigint::BigInt;
+use adventofcode_rust::duet::assemble::{self, Dialect};
+use adventofcode_rust::duet::bytecode::{Arithmetic, Event, Machine, Program, Word};
+use adventofcode_rust::duet::network::{Network, Outcome, Schedule, Status, Topology};
+use adventofcode_rust::duet::trace::{self, Record, Trace};
+use adventofcode_rust::duet::{self, decompile, transpile, Instruction, Number, Register};
+
+const USAGE: &str = "Usage: duet run FILE [--arithmetic checked|wrapping|saturating|big]
+                [REGISTER=VALUE...]
+       duet network FILE COUNT [--topology ring|pairs|broadcast|EDGES]
+                    [--schedule fair|random[:SEED]] [--max-turns N]
+       duet trace record FILE COUNT TRACE [network options]
+       duet trace replay FILE TRACE
+       duet trace diff TRACE TRACE
+       duet transpile FILE
+       duet compare FILE [REGISTER=VALUE...]
+       duet decompile FILE
+       duet cfg FILE
+       duet assemble FILE [--dialect day18|day23]";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
+
+fn load(path: &str) -> io::Result<Vec<Instruction>> {
+    let source = fs::read_to_string(path)?;
+    duet::parse(&source).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn parse_assignment(arg: &str) -> (Register, Number) {
+    let mut parts = arg.splitn(2, '=');
+    let register = parts.next().and_then(Register::new);
+    match (register, parts.next().and_then(|v| v.parse().ok())) {
+        (Some(r), Some(v)) => (r, v),
+        _ => {
+            eprintln!("Expected REGISTER=VALUE, got {}", arg);
+            process::exit(1);
+        }
+    }
+}
+
+/// Runs a single program on the bytecode interpreter until it halts, fails
+/// or waits for a message, and returns what it sent and its final state as
+/// lines.
+fn interpret<W: Word>(
+    instructions: &[Instruction],
+    assignments: &[&str],
+    arithmetic: Arithmetic,
+) -> (Vec<String>, u64, Duration) {
+    let program = Program::compile(instructions);
+    let mut machine = Machine::<W>::with_arithmetic(&program, arithmetic);
+    for a in assignments.iter() {
+        let (r, v) = parse_assignment(a);
+        machine.set(r, W::from_number(v));
+    }
+
+    let mut lines = Vec::new();
+    let start = Instant::now();
+    loop {
+        match machine.run() {
+            Event::Sent(v) => lines.push(format!("snd {}", v)),
+            Event::Receiving(_) => {
+                lines.push(format!(
+                    "Waiting for a message at instruction {}",
+                    machine.pc()
+                ));
+                break;
+            }
+            Event::Halted => break,
+            Event::Fault(fault) => {
+                lines.push(format!("{} at instruction {}", fault, machine.pc()));
+                break;
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let mut registers = program.registers().to_vec();
+    registers.sort();
+    let registers: Vec<String> = registers
+        .iter()
+        .map(|&r| format!("{}={}", r, machine.get(r)))
+        .collect();
+    lines.push(format!("Registers: {}", registers.join(" ")));
+    lines.push(format!("mul: {}", machine.muls));
+    (lines, machine.steps, elapsed)
+}
+
+fn run(path: &str, options: &[&str]) -> io::Result<()> {
+    let (arithmetic, assignments) = match options {
+        ["--arithmetic", arithmetic, rest @ ..] => (*arithmetic, rest),
+        _ => ("checked", options),
+    };
+    let instructions = load(path)?;
+    let (lines, steps, elapsed) = match arithmetic {
+        "checked" => interpret::<Number>(&instructions, assignments, Arithmetic::Checked),
+        "wrapping" => interpret::<Number>(&instructions, assignments, Arithmetic::Wrapping),
+        "saturating" => interpret::<Number>(&instructions, assignments, Arithmetic::Saturating),
+        "big" => interpret::<BigInt>(&instructions, assignments, Arithmetic::Checked),
+        _ => usage(),
+    };
+    for line in lines.iter() {
+        println!("{}", line);
+    }
+    println!(
+        "{} instructions executed in {:.3} s",
+        steps,
+        elapsed.as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Compiles the transpiled program with `rustc`, runs it and the interpreter
+/// with the same registers, and checks that they agree. The compiled program
+/// wraps around on overflow, so the interpreter does too.
+fn compare(path: &str, assignments: &[&str]) -> io::Result<()> {
+    let instructions = load(path)?;
+    let dir = env::temp_dir().join(format!("duet-{}", process::id()));
+    let build = transpile::build(&transpile::harness(&instructions), &dir)?;
+    let output = Command::new(&build.binary).args(assignments).output()?;
+    drop(build);
+    if !output.status.success() {
+        eprintln!(
+            "The compiled program failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        process::exit(1);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut native: Vec<String> = stdout.lines().map(String::from).collect();
+    let native_elapsed = match native.pop() {
+        Some(ref line) if line.starts_with("elapsed_us ") => {
+            Duration::from_micros(line["elapsed_us ".len()..].parse().unwrap_or(0))
+        }
+        _ => Duration::from_micros(0),
+    };
+
+    let (interpreted, _, interpreted_elapsed) =
+        interpret::<Number>(&instructions, assignments, Arithmetic::Wrapping);
+    println!("Interpreter: {:.3} s", interpreted_elapsed.as_secs_f64());
+    println!("Native:      {:.3} s", native_elapsed.as_secs_f64());
+
+    if native != interpreted {
+        let line = native
+            .iter()
+            .zip(interpreted.iter())
+            .position(|(n, i)| n != i)
+            .unwrap_or_else(|| native.len().min(interpreted.len()));
+        eprintln!(
+            "Results differ at line {}:\n  interpreter: {}\n  native:      {}",
+            line + 1,
+            interpreted.get(line).map(|s| s.as_str()).unwrap_or("<end>"),
+            native.get(line).map(|s| s.as_str()).unwrap_or("<end>")
+        );
+        process::exit(1);
+    }
+    println!("Results are identical ({} lines)", native.len());
+    Ok(())
+}
+
+struct NetworkOptions {
+    topology: Topology,
+    schedule: Schedule,
+    max_turns: Option<u64>,
+}
+
+fn parse_network_options(options: &[&str]) -> NetworkOptions {
+    let mut parsed = NetworkOptions {
+        topology: Topology::Ring,
+        schedule: Schedule::Fair,
+        max_turns: None,
+    };
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().unwrap_or_else(|| usage());
+        let result = match *option {
+            "--topology" => Topology::parse(value).map(|t| parsed.topology = t),
+            "--schedule" => Schedule::parse(value).map(|s| parsed.schedule = s),
+            "--max-turns" => value
+                .parse()
+                .map(|n| parsed.max_turns = Some(n))
+                .map_err(|_| format!("not a number: {}", value)),
+            _ => usage(),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+    parsed
+}
+
+fn build_network<'a>(program: &'a Program, count: usize, topology: &Topology) -> Network<'a> {
+    match Network::new(program, count, topology) {
+        Ok(network) => network,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn print_network(network: &Network, outcome: Outcome, max_turns: Option<u64>) {
+    println!("Program     Sent Received   Queued  State");
+    for p in 0..network.len() {
+        let state = match network.status(p) {
+            Status::Running => format!("running at {}", network.machine(p).pc()),
+            Status::Waiting => format!("waiting at {}", network.machine(p).pc()),
+            Status::Halted => String::from("halted"),
+            Status::Failed(fault) => format!("{} at {}", fault, network.machine(p).pc()),
+        };
+        println!(
+            "{:>7} {:>8} {:>8} {:>8}  {}",
+            p,
+            network.sent[p],
+            network.received[p],
+            network.queue(p).len(),
+            state
+        );
+    }
+    match outcome {
+        Outcome::Halted => println!("All programs halted"),
+        Outcome::Deadlock(waiting) => {
+            let waiting: Vec<String> = waiting.iter().map(|p| p.to_string()).collect();
+            println!("Deadlock: programs {} are waiting", waiting.join(", "))
+        }
+        Outcome::TurnLimit => println!("Stopped after {} turns", max_turns.unwrap_or(0)),
+    }
+}
+
+/// Runs `count` copies of a program wired together, and prints what every
+/// program did.
+fn network(path: &str, count: &str, options: &[&str]) -> io::Result<()> {
+    let count: usize = count.parse().unwrap_or_else(|_| usage());
+    let options = parse_network_options(options);
+    let program = Program::compile(&load(path)?);
+    let mut network = build_network(&program, count, &options.topology);
+
+    let start = Instant::now();
+    let outcome = network.run(&options.schedule, options.max_turns);
+    let elapsed = start.elapsed();
+
+    print_network(&network, outcome, options.max_turns);
+    println!("Ran for {:.3} s", elapsed.as_secs_f64());
+    Ok(())
+}
+
+/// Runs a network like `network` and saves the trace of the run.
+fn trace_record(path: &str, count: &str, trace_path: &str, options: &[&str]) -> io::Result<()> {
+    let count: usize = count.parse().unwrap_or_else(|_| usage());
+    let options = parse_network_options(options);
+    let program = Program::compile(&load(path)?);
+    let mut network = build_network(&program, count, &options.topology);
+
+    let mut records = Vec::new();
+    let outcome = network.run_traced(&options.schedule, options.max_turns, Some(&mut records));
+    print_network(&network, outcome, options.max_turns);
+
+    let trace = Trace {
+        programs: count,
+        topology: options.topology,
+        records,
+    };
+    trace.save(Path::new(trace_path))?;
+    println!("Saved {} records to {}", trace.records.len(), trace_path);
+    Ok(())
+}
+
+fn print_divergence(a: &[Record], b: &[Record], index: usize, names: [&str; 2]) {
+    println!("The traces diverge at record {}", index);
+    for (records, name) in [a, b].iter().zip(names.iter()) {
+        println!("{}:", name);
+        for line in trace::context(records, index, 5) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Runs the network again giving turns in the order of the trace, and checks
+/// that the run is the same.
+fn trace_replay(path: &str, trace_path: &str) -> io::Result<()> {
+    let recorded = Trace::load(Path::new(trace_path))?;
+    let program = Program::compile(&load(path)?);
+    let mut network = build_network(&program, recorded.programs, &recorded.topology);
+
+    let mut records = Vec::new();
+    let outcome = network.run_traced(
+        &Schedule::Replay(recorded.turns()),
+        None,
+        Some(&mut records),
+    );
+    print_network(&network, outcome, None);
+
+    match trace::first_divergence(&recorded.records, &records) {
+        None => println!("The replay matches all {} records", records.len()),
+        Some(index) => {
+            print_divergence(&recorded.records, &records, index, ["recorded", "replayed"]);
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn trace_diff(a: &str, b: &str) -> io::Result<()> {
+    let (first, second) = (Trace::load(Path::new(a))?, Trace::load(Path::new(b))?);
+    match trace::first_divergence(&first.records, &second.records) {
+        None => println!("The traces are identical ({} records)", first.records.len()),
+        Some(index) => {
+            print_divergence(&first.records, &second.records, index, [a, b]);
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Assembles a structured program and prints the instructions, which the
+/// parser of the day has read back.
+fn assemble(path: &str, dialect: &str) -> io::Result<()> {
+    let source = fs::read_to_string(path)?;
+    let result = Dialect::parse(dialect).and_then(|dialect| {
+        let program = assemble::assemble(&source, dialect)?;
+        assemble::to_text(&program, dialect)
+    });
+    match result {
+        Ok(text) => print!("{}", text),
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    match args.as_slice() {
+        ["run", path, options @ ..] => run(path, options)?,
+        ["network", path, count, options @ ..] => network(path, count, options)?,
+        ["trace", "record", path, count, trace_path, options @ ..] => {
+            trace_record(path, count, trace_path, options)?
+        }
+        ["trace", "replay", path, trace_path] => trace_replay(path, trace_path)?,
+        ["trace", "diff", a, b] => trace_diff(a, b)?,
+        ["transpile", path] => print!("{}", transpile::transpile(&load(path)?)),
+        ["compare", path, assignments @ ..] => compare(path, assignments)?,
+        ["decompile", path] => print!("{}", decompile::decompile(&load(path)?)),
+        ["cfg", path] => print!("{}", decompile::control_flow_graph(&load(path)?)),
+        ["assemble", path] => assemble(path, "day18")?,
+        ["assemble", path, "--dialect", dialect] => assemble(path, dialect)?,
+        _ => usage(),
+    }
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::solver::{Answer, Parameters, Solver};
+use crate::tsv::{escape, unescape};
+
+pub const DEFAULT_PATH: &str = ".cache/answers.tsv";
+
+/// The cached answer to one part of a day, along with everything that was
+/// used to compute it.
+pub struct Entry {
+    pub day: u32,
+    pub part: usize,
+    pub version: u32,
+    pub input_hash: String,
+    pub parameters: String,
+    pub description: String,
+    pub value: String,
+}
+
+impl Entry {
+    fn matches(&self, day: u32, version: u32, input_hash: &str, parameters: &str) -> bool {
+        self.day == day
+            && self.version == version
+            && self.input_hash == input_hash
+            && self.parameters == parameters
+    }
+
+    fn to_line(&self) -> String {
+        let fields = [
+            self.day.to_string(),
+            self.part.to_string(),
+            self.version.to_string(),
+            escape(&self.input_hash),
+            escape(&self.parameters),
+            escape(&self.description),
+            escape(&self.value),
+        ];
+        fields.join("\t")
+    }
+
+    fn from_line(line: &str) -> Option<Entry> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return None;
+        }
+        Some(Entry {
+            day: fields[0].parse().ok()?,
+            part: fields[1].parse().ok()?,
+            version: fields[2].parse().ok()?,
+            input_hash: unescape(fields[3]),
+            parameters: unescape(fields[4]),
+            description: unescape(fields[5]),
+            value: unescape(fields[6]),
+        })
+    }
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust versions,
+/// so that cached answers and checkpoints stay valid.
+pub fn hash_input(input: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in input.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+pub fn format_parameters(params: &Parameters) -> String {
+    let params: Vec<String> = params
+        .to_vec()
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    if params.is_empty() {
+        String::from("-")
+    } else {
+        params.join(",")
+    }
+}
+
+/// A persistent map from (day, part, solver version, input hash, parameters)
+/// to the answer, stored as a tab separated file with one entry per line.
+pub struct Cache {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl Cache {
+    /// Opens the cache at `path`. A missing file is an empty cache and lines
+    /// that cannot be read are dropped, as any entry can be recomputed.
+    pub fn open(path: &Path) -> io::Result<Cache> {
+        let entries = match fs::read_to_string(path) {
+            Ok(contents) => contents.lines().filter_map(Entry::from_line).collect(),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Cache {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn lookup(&self, solver: &Solver, input: &str, params: &Parameters) -> Option<Vec<Answer>> {
+        let input_hash = hash_input(input);
+        let parameters = format_parameters(params);
+        let mut entries: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|e| e.matches(solver.day, solver.version, &input_hash, &parameters))
+            .collect();
+        if entries.is_empty() {
+            return None;
+        }
+        entries.sort_by_key(|e| e.part);
+        Some(
+            entries
+                .iter()
+                .map(|e| Answer::new(&e.description, &e.value))
+                .collect(),
+        )
+    }
+
+    pub fn insert(
+        &mut self,
+        solver: &Solver,
+        input: &str,
+        params: &Parameters,
+        answers: &[Answer],
+    ) {
+        let input_hash = hash_input(input);
+        let parameters = format_parameters(params);
+        self.entries
+            .retain(|e| !e.matches(solver.day, solver.version, &input_hash, &parameters));
+        for (i, answer) in answers.iter().enumerate() {
+            self.entries.push(Entry {
+                day: solver.day,
+                part: i + 1,
+                version: solver.version,
+                input_hash: input_hash.clone(),
+                parameters: parameters.clone(),
+                description: answer.description.clone(),
+                value: answer.value.clone(),
+            });
+        }
+    }
+
+    /// Removes the entries for the given days, or all entries if no days are
+    /// given, and returns the number of entries removed.
+    pub fn clear(&mut self, days: &[u32]) -> usize {
+        let count = self.entries.len();
+        self.entries
+            .retain(|e| !days.is_empty() && !days.contains(&e.day));
+        count - self.entries.len()
+    }
+
+    /// Writes the cache to a temporary file first so that a concurrent run
+    /// never sees a half-written cache.
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut contents = String::new();
+        for e in self.entries.iter() {
+            contents.push_str(&e.to_line());
+            contents.push('\n');
+        }
+        let tmp_path = self
+            .path
+            .with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp_path, contents)?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
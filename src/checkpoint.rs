@@ -0,0 +1,181 @@
+//! Files that save the state of a long simulation, so that it can resume
+//! after being stopped. A checkpoint starts with a line naming its kind and
+//! the version of its format, followed by `name=value` fields, and the lines
+//! after that are up to the simulation.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[derive(Default)]
+pub struct Checkpoint {
+    fields: Vec<(String, String)>,
+    pub lines: Vec<String>,
+}
+
+impl Checkpoint {
+    pub fn new() -> Checkpoint {
+        Checkpoint::default()
+    }
+
+    pub fn set<T: ToString>(&mut self, name: &str, value: T) {
+        self.fields.push((name.to_string(), value.to_string()));
+    }
+
+    pub fn get<T: FromStr>(&self, name: &str) -> io::Result<T> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, v)| v.parse().ok())
+            .ok_or_else(|| invalid(format!("missing or invalid field {}", name)))
+    }
+
+    /// The whole file, as `save` writes it.
+    pub fn contents(&self, kind: &str, version: u32) -> String {
+        let mut contents = format!("{} {}", kind, version);
+        for (name, value) in self.fields.iter() {
+            contents.push_str(&format!(" {}={}", name, value));
+        }
+        contents.push('\n');
+        for line in self.lines.iter() {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        contents
+    }
+
+    /// Writes the checkpoint next to `path` first, and then moves it there,
+    /// so that being stopped while saving leaves the previous one intact.
+    pub fn save(&self, path: &Path, kind: &str, version: u32) -> io::Result<()> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let mut file = io::BufWriter::new(fs::File::create(&partial)?);
+        file.write_all(self.contents(kind, version).as_bytes())?;
+        file.into_inner()?.sync_all()?;
+        fs::rename(&partial, path)
+    }
+
+    pub fn load(path: &Path, kind: &str, version: u32) -> io::Result<Checkpoint> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        let mut header = lines.next().unwrap_or("").split_whitespace();
+        if header.next() != Some(kind) {
+            return Err(invalid(format!("not a {} file", kind)));
+        }
+        match header.next().map(|v| v.parse::<u32>()) {
+            Some(Ok(v)) if v == version => {}
+            Some(Ok(v)) => {
+                return Err(invalid(format!(
+                    "version {} is not supported, only version {}",
+                    v, version
+                )))
+            }
+            _ => return Err(invalid(String::from("invalid header"))),
+        }
+
+        let fields = header
+            .map(|field| {
+                let mut parts = field.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(name), Some(value)) => Ok((name.to_string(), value.to_string())),
+                    _ => Err(invalid(format!("invalid field `{}`", field))),
+                }
+            })
+            .collect::<io::Result<Vec<(String, String)>>>()?;
+        Ok(Checkpoint {
+            fields,
+            lines: lines.map(String::from).collect(),
+        })
+    }
+}
+
+/// When a simulation saves checkpoints and where, and the one it resumes
+/// from.
+pub struct Schedule {
+    /// The number of steps between checkpoints, or `None` for none.
+    pub every: Option<u64>,
+    pub path: PathBuf,
+    pub resume: Option<PathBuf>,
+}
+
+impl Schedule {
+    pub fn new(path: &str) -> Schedule {
+        Schedule {
+            every: None,
+            path: PathBuf::from(path),
+            resume: None,
+        }
+    }
+
+    /// The step to run until from `step`: the next one with a checkpoint,
+    /// but no further than `end`.
+    pub fn next_stop(&self, step: u64, end: u64) -> u64 {
+        match self.every {
+            Some(every) => ((step / every + 1) * every).min(end),
+            None => end,
+        }
+    }
+
+    pub fn is_due(&self, step: u64) -> bool {
+        self.every.is_some_and(|every| step.is_multiple_of(every))
+    }
+}
+
+/// A file in the temporary directory for checkpoints of the given kind,
+/// which no other process uses.
+pub fn temp_path(kind: &str) -> PathBuf {
+    env::temp_dir().join(format!("{}-{}", kind, process::id()))
+}
+
+/// Bytes as pairs of hexadecimal digits.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("checkpoint-test");
+        let mut checkpoint = Checkpoint::new();
+        checkpoint.set("steps", 12386363);
+        checkpoint.set("state", "A");
+        checkpoint.lines = vec![to_hex(&[0, 1, 254, 255]), String::new()];
+        checkpoint.save(&path, "test-checkpoint", 3).unwrap();
+
+        let loaded = Checkpoint::load(&path, "test-checkpoint", 3).unwrap();
+        assert_eq!(loaded.get::<u64>("steps").unwrap(), 12386363);
+        assert_eq!(loaded.get::<String>("state").unwrap(), "A");
+        assert!(loaded.get::<u64>("state").is_err());
+        assert!(loaded.get::<u64>("missing").is_err());
+        assert_eq!(
+            loaded.contents("test-checkpoint", 3),
+            checkpoint.contents("test-checkpoint", 3)
+        );
+        assert_eq!(from_hex(&loaded.lines[0]).unwrap(), vec![0, 1, 254, 255]);
+
+        assert!(Checkpoint::load(&path, "other-checkpoint", 3).is_err());
+        assert!(Checkpoint::load(&path, "test-checkpoint", 2).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
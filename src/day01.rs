@@ -0,0 +1,29 @@
+use crate::solver::{end_part, Answer, Parameters};
+
+fn solve_captcha(input: &[u8], offset: usize) -> u32 {
+    let mut solution: u32 = 0;
+    for (i, digit) in input.iter().enumerate() {
+        if *digit == input[(i + offset) % input.len()] {
+            solution += *digit as u32;
+        }
+    }
+    solution
+}
+
+pub fn solve(input: &str, _params: &Parameters) -> Vec<Answer> {
+    let input: Vec<u8> = input.bytes().map(|b| b - b'0').collect();
+
+    let part1 = Answer::new(
+        "The solution to the first captcha is",
+        solve_captcha(&input, 1),
+    );
+    end_part();
+
+    vec![
+        part1,
+        Answer::new(
+            "The solution to the second captcha is",
+            solve_captcha(&input, input.len() / 2),
+        ),
+    ]
+}
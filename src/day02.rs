@@ -0,0 +1,55 @@
+use nom::{
+    character::complete::{char, digit1},
+    combinator::{map_res, verify},
+    multi::separated_list,
+    IResult,
+};
+
+use crate::solver::{Answer, Parameters};
+
+fn parse_u32(input: &str) -> IResult<&str, u32> {
+    map_res(digit1, str::parse::<u32>)(input)
+}
+
+fn parse_row(input: &str) -> IResult<&str, Vec<u32>> {
+    let f = |v: &Vec<u32>| !v.is_empty();
+    verify(separated_list(char('\t'), parse_u32), f)(input)
+}
+
+pub fn solve(input: &str, _params: &Parameters) -> Vec<Answer> {
+    let (_rest, input) = separated_list(char('\n'), parse_row)(input).unwrap();
+
+    let mut checksum = 0;
+    let mut result_sum = 0;
+    for v in input.iter() {
+        let mut min = u32::MAX;
+        let mut max = u32::MIN;
+
+        for i in v.iter() {
+            if *i < min {
+                min = *i;
+            }
+            if *i > max {
+                max = *i;
+            }
+        }
+
+        checksum += max - min;
+
+        let mut sorted = v.clone();
+        sorted.sort();
+        'result_loop: for i in 0..sorted.len() {
+            for j in i + 1..sorted.len() {
+                if sorted[j] % sorted[i] == 0 {
+                    result_sum += sorted[j] / sorted[i];
+                    break 'result_loop;
+                }
+            }
+        }
+    }
+
+    vec![
+        Answer::new("The spreadsheet's checksum is", checksum),
+        Answer::new("The sum of each row's result is", result_sum),
+    ]
+}
@@ -0,0 +1,34 @@
+use crate::solver::{end_part, Answer, Parameters};
+
+fn get_number_of_steps(input: &[i32], part2: bool) -> u32 {
+    let mut input = input.to_vec();
+    let mut pointer: usize = 0;
+    let mut count: u32 = 0;
+
+    while pointer < input.len() {
+        let increment = if part2 && input[pointer] >= 3 { -1 } else { 1 };
+        input[pointer] += increment;
+        pointer = (pointer as i32 + input[pointer] - increment) as usize;
+        count += 1;
+    }
+
+    count
+}
+
+pub fn solve(input: &str, _params: &Parameters) -> Vec<Answer> {
+    let input: Vec<i32> = input.lines().map(|line| line.parse().unwrap()).collect();
+
+    let part1 = Answer::new(
+        "Number of steps to reach the exit (part 1)",
+        get_number_of_steps(&input, false),
+    );
+    end_part();
+
+    vec![
+        part1,
+        Answer::new(
+            "Number of steps to reach the exit (part 2)",
+            get_number_of_steps(&input, true),
+        ),
+    ]
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use crate::solver::{end_part, Answer, Parameters};
+
+type MemoryBankSet = [u32; 16];
+
+fn redistribute_memory(input: &MemoryBankSet) -> MemoryBankSet {
+    let mut new_memory_bank_set = *input;
+    let mut bank_index = 0;
+    let mut bank_count = 0;
+
+    for (i, count) in input.iter().enumerate() {
+        if i == 0 || *count > bank_count {
+            bank_count = *count;
+            bank_index = i;
+        }
+    }
+
+    new_memory_bank_set[bank_index] = 0;
+    bank_index += 1;
+    bank_index %= 16;
+
+    while bank_count > 0 {
+        new_memory_bank_set[bank_index] += 1;
+        bank_count -= 1;
+        bank_index += 1;
+        bank_index %= 16;
+    }
+
+    new_memory_bank_set
+}
+
+pub fn solve(input: &str, _params: &Parameters) -> Vec<Answer> {
+    let mut answers = Vec::new();
+    let mut banks: MemoryBankSet = [0; 16];
+    let mut states_found: HashSet<MemoryBankSet> = HashSet::new();
+    states_found.insert(banks);
+
+    for line in input.lines() {
+        for (i, number) in line.split('\t').enumerate() {
+            banks[i] = number.parse().unwrap();
+        }
+    }
+
+    let mut num_cycles = 0;
+    let mut part1_done = false;
+    loop {
+        num_cycles += 1;
+        banks = redistribute_memory(&banks);
+        if !states_found.insert(banks) {
+            if part1_done {
+                break;
+            }
+
+            answers.push(Answer::new(
+                "Number of cycles before start of infinite loop",
+                num_cycles,
+            ));
+            end_part();
+            states_found = HashSet::new();
+            num_cycles = 0;
+            part1_done = true;
+        }
+    }
+    answers.push(Answer::new(
+        "Number of cycles in infinite loop",
+        num_cycles - 1,
+    ));
+
+    answers
+}
@@ -0,0 +1,52 @@
+use crate::solver::{end_part, Answer, Parameters};
+
+struct Layer {
+    depth: u32,
+    range: u32,
+}
+
+fn caught(l: &Layer, delay: u32) -> bool {
+    (l.depth + delay).is_multiple_of(2 * l.range - 2)
+}
+
+fn trip_severity(input: &[Layer], delay: u32) -> u32 {
+    input
+        .iter()
+        .filter(|l| caught(l, delay))
+        .fold(0, |a, e| a + e.depth * e.range)
+}
+
+fn trip_caught(input: &[Layer], delay: u32) -> bool {
+    input.iter().any(|l| caught(l, delay))
+}
+
+pub fn solve(input: &str, _params: &Parameters) -> Vec<Answer> {
+    let mut answers = Vec::new();
+    let mut layers = Vec::new();
+
+    for line in input.lines() {
+        let mut split = line.split(": ");
+
+        let depth = split.next().unwrap().parse().unwrap();
+        let range = split.next().unwrap().parse().unwrap();
+        layers.push(Layer { depth, range });
+    }
+
+    answers.push(Answer::new(
+        "The severity of the trip with delay 0",
+        trip_severity(&layers, 0),
+    ));
+    end_part();
+
+    let mut delay = 1;
+    while trip_caught(&layers, delay) {
+        delay += 1;
+    }
+
+    answers.push(Answer::new(
+        "The smallest delay for which you don't get caught",
+        delay,
+    ));
+
+    answers
+}
@@ -0,0 +1,60 @@
+use crate::solver::{end_part, Answer, Parameter, Parameters};
+
+pub const PARAMETERS: &[Parameter] = &[
+    Parameter {
+        name: "pairs_part1",
+        default: "40000000",
+        description: "Number of pairs the judge considers in part 1",
+    },
+    Parameter {
+        name: "pairs_part2",
+        default: "5000000",
+        description: "Number of pairs the judge considers in part 2",
+    },
+];
+
+fn count_matches(input: &[u64], iteration_count: u64, part2: bool) -> u64 {
+    let multipliers: Vec<u64> = vec![16807, 48271];
+    let modulus_per_generator: Vec<u64> = vec![4, 8];
+    let modulus: u64 = 2147483647;
+
+    let mut count = 0;
+    let mut input = input.to_vec();
+
+    for _iteration in 0..iteration_count {
+        for i in 0..=1 {
+            loop {
+                input[i] *= multipliers[i];
+                input[i] %= modulus;
+                if !part2 || input[i].is_multiple_of(modulus_per_generator[i]) {
+                    break;
+                }
+            }
+        }
+        if input[0] % 65536 == input[1] % 65536 {
+            count += 1;
+        }
+    }
+    count
+}
+
+pub fn solve(input: &str, params: &Parameters) -> Vec<Answer> {
+    let input: Vec<u64> = input
+        .lines()
+        .map(|line| line[24..].parse().unwrap())
+        .collect();
+
+    let part1 = Answer::new(
+        "Final count (part 1)",
+        count_matches(&input, params.get("pairs_part1"), false),
+    );
+    end_part();
+
+    vec![
+        part1,
+        Answer::new(
+            "Final count (part 2)",
+            count_matches(&input, params.get("pairs_part2"), true),
+        ),
+    ]
+}
@@ -0,0 +1,121 @@
+use nom::{
+    branch::alt,
+    character::complete::{char, digit1, one_of},
+    combinator::{map, map_res},
+    multi::separated_list,
+    sequence::{preceded, separated_pair},
+    IResult,
+};
+
+use crate::solver::{end_part, Answer, Parameter, Parameters};
+
+pub const PARAMETERS: &[Parameter] = &[Parameter {
+    name: "dances",
+    default: "1000000000",
+    description: "Number of dances performed in part 2",
+}];
+
+enum Instruction {
+    Spin(usize),
+    Exchange(usize, usize),
+    Partner(char, char),
+}
+use Instruction::*;
+
+impl Instruction {
+    fn process(&self, mut programs: Vec<char>) -> Vec<char> {
+        match *self {
+            Spin(s) => {
+                let pos = programs.len() - s;
+                let mut temp = programs.drain(0..pos).collect();
+                programs.append(&mut temp);
+            }
+            Exchange(p, q) => {
+                programs.swap(p, q);
+            }
+            Partner(p, q) => {
+                let mut i = 0;
+                let mut j = 0;
+                while programs[i] != p {
+                    i += 1;
+                }
+                while programs[j] != q {
+                    j += 1;
+                }
+                programs.swap(i, j);
+            }
+        }
+        programs
+    }
+}
+
+fn parse_usize(input: &str) -> IResult<&str, usize> {
+    map_res(digit1, str::parse::<usize>)(input)
+}
+
+fn parse_spin(input: &str) -> IResult<&str, Instruction> {
+    map(preceded(char('s'), parse_usize), Spin)(input)
+}
+
+fn parse_exchange(input: &str) -> IResult<&str, Instruction> {
+    let f = |(p, q): (usize, usize)| Exchange(p, q);
+    let parser = separated_pair(parse_usize, char('/'), parse_usize);
+    map(preceded(char('x'), parser), f)(input)
+}
+
+fn parse_program(input: &str) -> IResult<&str, char> {
+    one_of("abcdefghijklmnop")(input)
+}
+
+fn parse_partner(input: &str) -> IResult<&str, Instruction> {
+    let f = |(p, q): (char, char)| Partner(p, q);
+    let parser = separated_pair(parse_program, char('/'), parse_program);
+    map(preceded(char('p'), parser), f)(input)
+}
+
+fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
+    alt((parse_spin, parse_exchange, parse_partner))(input)
+}
+
+fn parse_instructions(input: &str) -> IResult<&str, Vec<Instruction>> {
+    separated_list(char(','), parse_instruction)(input)
+}
+
+pub fn solve(input: &str, params: &Parameters) -> Vec<Answer> {
+    let mut answers = Vec::new();
+    let num_dances: usize = params.get("dances");
+    let input = parse_instructions(input).unwrap().1;
+
+    let mut programs = vec![
+        'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p',
+    ];
+    let mut solutions = Vec::new();
+    let mut iteration = 0;
+    let mut done = false;
+
+    while !done {
+        for i in input.iter() {
+            programs = i.process(programs);
+        }
+
+        let output: String = programs.iter().collect();
+        if iteration == 0 {
+            answers.push(Answer::new("Order after first dance", &output));
+            end_part();
+        }
+
+        if output == "abcdefghijklmnop" {
+            done = true;
+        }
+
+        iteration += 1;
+        solutions.push(output);
+    }
+
+    answers.push(Answer::new(
+        &format!("Order after {} dances", num_dances),
+        &solutions[(num_dances + solutions.len() - 1) % solutions.len()],
+    ));
+
+    answers
+}
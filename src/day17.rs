@@ -0,0 +1,57 @@
+use crate::solver::{end_part, Answer, Parameter, Parameters};
+
+pub const PARAMETERS: &[Parameter] = &[
+    Parameter {
+        name: "insertions_part1",
+        default: "2017",
+        description: "Number of values inserted into the buffer in part 1",
+    },
+    Parameter {
+        name: "insertions_part2",
+        default: "50000000",
+        description: "Number of values inserted into the buffer in part 2",
+    },
+];
+
+pub fn solve(input: &str, params: &Parameters) -> Vec<Answer> {
+    let input: usize = input.parse().unwrap();
+    let insertions_part1: usize = params.get("insertions_part1");
+    let insertions_part2: usize = params.get("insertions_part2");
+
+    let mut buffer: Vec<u32> = Vec::with_capacity(insertions_part1 + 1);
+    buffer.push(0);
+
+    let mut current_pos: usize = 0;
+
+    for i in 1..=insertions_part1 {
+        current_pos += input;
+        current_pos %= i;
+        current_pos += 1;
+        buffer.insert(current_pos, i as u32);
+    }
+
+    let part1 = Answer::new(
+        &format!("The value after {} is", insertions_part1),
+        buffer[(current_pos + 1) % buffer.len()],
+    );
+    end_part();
+
+    current_pos = 0;
+    let mut current_after_0 = 0;
+
+    for i in 1..=insertions_part2 {
+        current_pos += input;
+        current_pos %= i;
+        current_pos += 1;
+        if current_pos == 1 {
+            current_after_0 = i;
+        }
+    }
+
+    let part2 = Answer::new(
+        &format!("The value after 0 after {} iterations is", insertions_part2),
+        current_after_0,
+    );
+
+    vec![part1, part2]
+}
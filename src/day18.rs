@@ -0,0 +1,47 @@
+use crate::duet::{
+    self,
+    bytecode::{self, Event, Machine},
+    network::{Network, Schedule, Topology},
+    Number,
+};
+use crate::solver::{end_part, Answer, Parameters};
+
+pub mod debugger;
+
+/// Runs the program with `snd` playing a sound and `rcv` recovering the last
+/// sound played if its register is not zero.
+fn recover_frequency(program: &bytecode::Program) -> Number {
+    let mut machine = Machine::new(program);
+    let mut last_sound = None;
+    loop {
+        match machine.run() {
+            Event::Sent(frequency) => last_sound = Some(frequency),
+            Event::Receiving(0) => machine.skip(),
+            Event::Receiving(_) => return last_sound.unwrap(),
+            Event::Halted => panic!("the program ended without recovering a frequency"),
+            Event::Fault(fault) => panic!("{} at instruction {}", fault, machine.pc()),
+        }
+    }
+}
+
+/// Runs two copies of the program that send each other messages until both
+/// have ended or are waiting for a message, and returns how many messages
+/// program 1 sent.
+fn run_duet(program: &bytecode::Program) -> usize {
+    let mut network = Network::new(program, 2, &Topology::Pairs).unwrap();
+    network.run(&Schedule::Fair, None);
+    network.sent[1]
+}
+
+pub fn solve(input: &str, _params: &Parameters) -> Vec<Answer> {
+    let program =
+        bytecode::Program::compile(&duet::parse(input).unwrap_or_else(|e| panic!("{}", e)));
+
+    let part1 = Answer::new("The first frequency recovered", recover_frequency(&program));
+    end_part();
+
+    vec![
+        part1,
+        Answer::new("Number of messages sent by program 1", run_duet(&program)),
+    ]
+}
@@ -0,0 +1,422 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use crate::duet::{
+    self,
+    bytecode::{Arithmetic, Event, Fault, Machine, Program},
+    Instruction, Number, Register,
+};
+
+/// The number of executed instructions kept for `history`.
+const HISTORY_SIZE: usize = 1000;
+
+const HELP: &str = "Commands:
+  step [P] [N]      execute N instructions of program P (default: program 0, 1 instruction)
+  continue          run both programs in turns until a breakpoint, watchpoint or deadlock
+  break IDX         stop when a program is about to execute instruction IDX
+  delete IDX        remove the breakpoint on instruction IDX
+  watch P REG       stop when register REG of program P changes
+  unwatch P REG     remove a watchpoint
+  info              show breakpoints, watchpoints and the state of both programs
+  regs [P]          show the registers of program P, or of both programs
+  queue [P]         show the message queue of program P, or of both programs
+  history [N]       show the last N executed instructions, most recent first
+  list [P]          show the instructions around the current one of program P
+  quit              leave the debugger
+An empty line repeats the previous command.";
+
+struct Executed {
+    step: usize,
+    program: usize,
+    sp: usize,
+    instruction: String,
+}
+
+/// Runs two Duet programs like `solve` does, but under user control.
+struct Debugger<'a> {
+    instructions: &'a [Instruction],
+    /// The registers the program uses, sorted by name.
+    registers: Vec<Register>,
+    machines: [Machine<'a>; 2],
+    queues: [VecDeque<Number>; 2],
+    faults: [Option<Fault>; 2],
+    sent: [usize; 2],
+    steps: usize,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: Vec<(usize, Register)>,
+    history: VecDeque<Executed>,
+}
+
+impl<'a> Debugger<'a> {
+    fn new(
+        instructions: &'a [Instruction],
+        program: &'a Program,
+        arithmetic: Arithmetic,
+    ) -> Debugger<'a> {
+        let p = Register::new("p").unwrap();
+        let mut machines = [
+            Machine::with_arithmetic(program, arithmetic),
+            Machine::with_arithmetic(program, arithmetic),
+        ];
+        machines[1].set(p, 1);
+        let mut registers = program.registers().to_vec();
+        registers.sort();
+        Debugger {
+            instructions,
+            registers,
+            machines,
+            queues: [VecDeque::new(), VecDeque::new()],
+            faults: [None, None],
+            sent: [0, 0],
+            steps: 0,
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    /// A program has terminated when it jumped out of the program or an
+    /// instruction failed.
+    fn is_terminated(&self, p: usize) -> bool {
+        self.machines[p].pc() >= self.instructions.len() || self.faults[p].is_some()
+    }
+
+    /// A program is blocked when it is about to receive from an empty queue.
+    fn is_blocked(&self, p: usize) -> bool {
+        !self.is_terminated(p)
+            && matches!(
+                self.instructions[self.machines[p].pc()],
+                Instruction::Rcv(_)
+            )
+            && self.queues[p].is_empty()
+    }
+
+    fn register(&self, p: usize, r: Register) -> Number {
+        self.machines[p].get(r)
+    }
+
+    fn describe_state(&self, p: usize) -> String {
+        if let Some(fault) = self.faults[p] {
+            format!("stopped by {}", fault)
+        } else if self.is_terminated(p) {
+            String::from("terminated")
+        } else if self.is_blocked(p) {
+            String::from("waiting for a message")
+        } else {
+            String::from("running")
+        }
+    }
+
+    /// Executes one instruction of program `p` and returns the reason to stop,
+    /// if any.
+    fn step(&mut self, p: usize) -> Option<String> {
+        if let Some(fault) = self.faults[p] {
+            return Some(format!(
+                "Program {} stopped by {} at instruction {}",
+                p,
+                fault,
+                self.machines[p].pc()
+            ));
+        }
+        if self.is_terminated(p) {
+            return Some(format!("Program {} has terminated", p));
+        }
+        if self.is_blocked(p) {
+            return Some(format!("Program {} is waiting for a message", p));
+        }
+
+        let watched: Vec<(Register, Number)> = self
+            .watchpoints
+            .iter()
+            .filter(|(wp, _)| *wp == p)
+            .map(|&(_, r)| (r, self.register(p, r)))
+            .collect();
+
+        let sp = self.machines[p].pc();
+        if self.history.len() == HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        self.history.push_back(Executed {
+            step: self.steps,
+            program: p,
+            sp,
+            instruction: self.instructions[sp].to_string(),
+        });
+        self.steps += 1;
+
+        match self.machines[p].step() {
+            Some(Event::Sent(message)) => {
+                self.sent[p] += 1;
+                self.queues[1 - p].push_back(message);
+            }
+            Some(Event::Receiving(_)) => {
+                let message = self.queues[p].pop_front().unwrap();
+                self.machines[p].receive(message);
+            }
+            Some(Event::Fault(fault)) => {
+                self.faults[p] = Some(fault);
+                return Some(format!(
+                    "Program {} stopped by {} at instruction {}",
+                    p, fault, sp
+                ));
+            }
+            Some(Event::Halted) | None => {}
+        }
+
+        for (r, old) in watched {
+            let new = self.register(p, r);
+            if new != old {
+                return Some(format!(
+                    "Watchpoint: register {} of program {} changed from {} to {} at instruction {}",
+                    r, p, old, new, sp
+                ));
+            }
+        }
+
+        let pc = self.machines[p].pc();
+        if !self.is_terminated(p) && self.breakpoints.contains(&pc) {
+            return Some(format!(
+                "Breakpoint: program {} is at instruction {}",
+                p, pc
+            ));
+        }
+
+        None
+    }
+
+    /// Runs both programs in turns, like `solve`, until there is a reason to
+    /// stop.
+    fn run(&mut self) -> String {
+        loop {
+            let mut progress = false;
+            for p in 0..2 {
+                if self.is_terminated(p) || self.is_blocked(p) {
+                    continue;
+                }
+                progress = true;
+                if let Some(reason) = self.step(p) {
+                    return reason;
+                }
+            }
+            if !progress {
+                return if let Some(p) = (0..2).find(|&p| self.faults[p].is_some()) {
+                    format!(
+                        "Program {} stopped by {} at instruction {}",
+                        p,
+                        self.faults[p].unwrap(),
+                        self.machines[p].pc()
+                    )
+                } else if self.is_terminated(0) && self.is_terminated(1) {
+                    String::from("Both programs have terminated")
+                } else {
+                    String::from("Deadlock: no program can make progress")
+                };
+            }
+        }
+    }
+
+    fn print_location(&self, p: usize) {
+        let pc = self.machines[p].pc();
+        match self.instructions.get(pc) {
+            Some(i) if self.faults[p].is_some() => {
+                println!("  p{} {:>4}: {} ({})", p, pc, i, self.describe_state(p))
+            }
+            Some(i) => println!("  p{} {:>4}: {}", p, pc, i),
+            None => println!("  p{} terminated", p),
+        }
+    }
+
+    fn print_registers(&self, p: usize) {
+        let registers: Vec<String> = self
+            .registers
+            .iter()
+            .map(|&r| format!("{}={}", r, self.register(p, r)))
+            .collect();
+        println!("  p{}: {}", p, registers.join(" "));
+    }
+
+    fn print_queue(&self, p: usize) {
+        let queue: Vec<String> = self.queues[p].iter().map(|m| m.to_string()).collect();
+        println!("  p{} ({} queued): {}", p, queue.len(), queue.join(" "));
+    }
+
+    fn print_info(&self) {
+        for p in 0..2 {
+            println!(
+                "  p{}: instruction {}, {}, {} messages sent",
+                p,
+                self.machines[p].pc(),
+                self.describe_state(p),
+                self.sent[p]
+            );
+        }
+        let breakpoints: Vec<String> = self.breakpoints.iter().map(|b| b.to_string()).collect();
+        println!("  breakpoints: {}", breakpoints.join(" "));
+        let watchpoints: Vec<String> = self
+            .watchpoints
+            .iter()
+            .map(|(p, r)| format!("p{}:{}", p, r))
+            .collect();
+        println!("  watchpoints: {}", watchpoints.join(" "));
+        println!("  {} instructions executed", self.steps);
+    }
+
+    fn print_history(&self, count: usize) {
+        for e in self.history.iter().rev().take(count) {
+            println!(
+                "  #{:<8} p{} {:>4}: {}",
+                e.step, e.program, e.sp, e.instruction
+            );
+        }
+    }
+
+    fn print_listing(&self, p: usize) {
+        let pc = self.machines[p].pc();
+        let start = pc.saturating_sub(5);
+        let end = (pc + 6).min(self.instructions.len());
+        for (i, instruction) in self.instructions[start..end].iter().enumerate() {
+            let i = start + i;
+            println!(
+                "{}{} {:>4}: {}",
+                if i == pc { "=>" } else { "  " },
+                if self.breakpoints.contains(&i) {
+                    "*"
+                } else {
+                    " "
+                },
+                i,
+                instruction
+            );
+        }
+    }
+
+    /// Executes one command and returns `false` when the debugger should quit.
+    fn execute(&mut self, command: &[&str]) -> Result<bool, String> {
+        match command {
+            ["quit"] | ["q"] => return Ok(false),
+            ["help"] | ["h"] => println!("{}", HELP),
+            ["step", args @ ..] | ["s", args @ ..] => {
+                let (p, n) = match args {
+                    [] => (0, 1),
+                    [p] => (parse_program(p)?, 1),
+                    [p, n] => (parse_program(p)?, parse_number(n)?),
+                    _ => return Err(String::from("usage: step [P] [N]")),
+                };
+                for _ in 0..n {
+                    if let Some(reason) = self.step(p) {
+                        println!("{}", reason);
+                        break;
+                    }
+                }
+                self.print_location(p);
+            }
+            ["continue"] | ["c"] => {
+                println!("{}", self.run());
+                self.print_location(0);
+                self.print_location(1);
+            }
+            ["break", index] | ["b", index] => {
+                self.breakpoints.insert(parse_number(index)?);
+            }
+            ["delete", index] | ["d", index] => {
+                if !self.breakpoints.remove(&parse_number(index)?) {
+                    return Err(format!("no breakpoint on instruction {}", index));
+                }
+            }
+            ["watch", p, r] | ["w", p, r] => {
+                let watchpoint = (parse_program(p)?, parse_register_name(r)?);
+                if !self.watchpoints.contains(&watchpoint) {
+                    self.watchpoints.push(watchpoint);
+                }
+            }
+            ["unwatch", p, r] => {
+                let watchpoint = (parse_program(p)?, parse_register_name(r)?);
+                let count = self.watchpoints.len();
+                self.watchpoints.retain(|w| *w != watchpoint);
+                if self.watchpoints.len() == count {
+                    return Err(format!("no watchpoint on register {} of program {}", r, p));
+                }
+            }
+            ["info"] | ["i"] => self.print_info(),
+            ["regs"] | ["r"] => {
+                self.print_registers(0);
+                self.print_registers(1);
+            }
+            ["regs", p] | ["r", p] => self.print_registers(parse_program(p)?),
+            ["queue"] => {
+                self.print_queue(0);
+                self.print_queue(1);
+            }
+            ["queue", p] => self.print_queue(parse_program(p)?),
+            ["history"] => self.print_history(10),
+            ["history", n] => self.print_history(parse_number(n)?),
+            ["list"] | ["l"] => self.print_listing(0),
+            ["list", p] | ["l", p] => self.print_listing(parse_program(p)?),
+            _ => return Err(String::from("unknown command, try `help`")),
+        }
+        Ok(true)
+    }
+}
+
+fn parse_program(s: &str) -> Result<usize, String> {
+    match s {
+        "0" => Ok(0),
+        "1" => Ok(1),
+        _ => Err(format!("no program {}, there are programs 0 and 1", s)),
+    }
+}
+
+fn parse_number(s: &str) -> Result<usize, String> {
+    s.parse().map_err(|_| format!("not a number: {}", s))
+}
+
+fn parse_register_name(s: &str) -> Result<Register, String> {
+    Register::new(s).ok_or_else(|| format!("not a register: {}", s))
+}
+
+/// Debugs the Duet program in `path`, reading commands from stdin. The
+/// registers use the given arithmetic, and a failed instruction stops its
+/// program.
+pub fn run(path: &Path, arithmetic: Arithmetic) -> io::Result<()> {
+    let source = fs::read_to_string(path)?;
+    let instructions =
+        duet::parse(&source).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let program = Program::compile(&instructions);
+
+    let mut debugger = Debugger::new(&instructions, &program, arithmetic);
+    println!(
+        "Loaded {} instructions, type `help` for a list of commands",
+        instructions.len()
+    );
+    debugger.print_location(0);
+    debugger.print_location(1);
+
+    let stdin = io::stdin();
+    let mut previous = String::new();
+    loop {
+        print!("(duet) ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            line = previous.clone();
+        }
+
+        let command: Vec<&str> = line.split_whitespace().collect();
+        if command.is_empty() {
+            continue;
+        }
+        match debugger.execute(&command) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(message) => println!("Error: {}", message),
+        }
+        previous = line;
+    }
+}
@@ -0,0 +1,89 @@
+use nom::character::is_alphabetic;
+
+use crate::solver::{Answer, Parameters};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+pub fn solve(input: &str, _params: &Parameters) -> Vec<Answer> {
+    let input: Vec<Vec<char>> = input
+        .split('\n')
+        .filter(|s| s.len() > 1)
+        .map(|s| s.chars().collect())
+        .collect();
+
+    let mut coords = (0, 0);
+    let mut dir = Direction::South;
+    let mut letters = String::new();
+    let mut step_count = 0;
+
+    let directions: Vec<(i32, i32, Direction)> = vec![
+        (0, -1, Direction::North),
+        (1, 0, Direction::East),
+        (0, 1, Direction::South),
+        (-1, 0, Direction::West),
+    ];
+
+    for (i, c) in input[0].iter().enumerate() {
+        if *c != ' ' {
+            coords = (i, 0);
+            break;
+        }
+    }
+
+    while letters.len() < 10 {
+        match input[coords.1][coords.0] {
+            '+' => {
+                for (e, n, d) in directions.iter() {
+                    let x = (coords.0 as i32 + e) as usize;
+                    let y = (coords.1 as i32 + n) as usize;
+
+                    if dir != d.opposite() && input[y][x] != ' ' {
+                        dir = *d;
+                        break;
+                    }
+                }
+            }
+            ' ' => unreachable!(),
+            c if is_alphabetic(c as u8) => letters.push(c),
+            _ => {}
+        }
+
+        match dir {
+            Direction::North => {
+                coords.1 -= 1;
+            }
+            Direction::East => {
+                coords.0 += 1;
+            }
+            Direction::South => {
+                coords.1 += 1;
+            }
+            Direction::West => {
+                coords.0 -= 1;
+            }
+        }
+        step_count += 1;
+    }
+
+    vec![
+        Answer::new("The letters as encountered by the packet", letters),
+        Answer::new("Total number of steps", step_count),
+    ]
+}
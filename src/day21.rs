@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::value,
+    multi::{many1, separated_list},
+    sequence::separated_pair,
+    IResult,
+};
+
+use crate::bigint::BigInt;
+use crate::solver::{end_part, Answer, Parameter, Parameters};
+
+pub const PARAMETERS: &[Parameter] = &[
+    Parameter {
+        name: "iterations_part1",
+        default: "5",
+        description: "Number of enhancement iterations in part 1",
+    },
+    Parameter {
+        name: "iterations_part2",
+        default: "18",
+        description: "Number of enhancement iterations in part 2",
+    },
+    Parameter {
+        name: "start",
+        default: ".#./..#/###",
+        description: "Pattern to start from, in the format of the rules",
+    },
+    Parameter {
+        name: "counting",
+        default: "true",
+        description: "Whether part 2 only counts the 3x3 squares instead of listing them",
+    },
+];
+
+/// Pixels row by row from the top, as written in the rules.
+type Grid = Vec<Vec<bool>>;
+
+/// A square of at most 4x4 pixels, with one bit per pixel row by row from
+/// the top left, the first one in the lowest bit.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Pattern {
+    size: u8,
+    bits: u16,
+}
+
+impl Pattern {
+    fn new(rows: &[Vec<bool>]) -> Pattern {
+        let size = rows.len();
+        assert!(size <= 4);
+        let mut bits = 0;
+        for (r, row) in rows.iter().enumerate() {
+            assert!(row.len() == size);
+            for (c, &on) in row.iter().enumerate() {
+                bits |= (on as u16) << (r * size + c);
+            }
+        }
+        Pattern {
+            size: size as u8,
+            bits,
+        }
+    }
+
+    fn get(&self, row: usize, col: usize) -> bool {
+        self.bits >> (row * self.size as usize + col) & 1 == 1
+    }
+
+    /// The pattern with the pixel at `(row, col)` moved to `f(row, col)`.
+    fn transform(&self, f: impl Fn(usize, usize) -> (usize, usize)) -> Pattern {
+        let n = self.size as usize;
+        let mut bits = 0;
+        for row in 0..n {
+            for col in 0..n {
+                let (r, c) = f(row, col);
+                bits |= (self.get(row, col) as u16) << (r * n + c);
+            }
+        }
+        Pattern {
+            size: self.size,
+            bits,
+        }
+    }
+
+    /// The pattern rotated a quarter turn clockwise.
+    fn rotate(&self) -> Pattern {
+        let n = self.size as usize;
+        self.transform(|row, col| (col, n - 1 - row))
+    }
+
+    /// The pattern mirrored left to right.
+    fn flip(&self) -> Pattern {
+        let n = self.size as usize;
+        self.transform(|row, col| (row, n - 1 - col))
+    }
+
+    /// The four rotations of the pattern and of its mirror image.
+    fn symmetries(&self) -> [Pattern; 8] {
+        let mut symmetries = [*self; 8];
+        symmetries[4] = self.flip();
+        for i in [1, 2, 3, 5, 6, 7].iter() {
+            symmetries[*i] = symmetries[i - 1].rotate();
+        }
+        symmetries
+    }
+
+    /// The smallest of the symmetries, which all of them share.
+    fn canonical(&self) -> Pattern {
+        *self.symmetries().iter().min().unwrap()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.size as usize;
+        for row in 0..n {
+            if row > 0 {
+                write!(f, "/")?;
+            }
+            for col in 0..n {
+                write!(f, "{}", if self.get(row, col) { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The side of a grid, if it is square.
+fn square_size(grid: &[Vec<bool>]) -> Option<usize> {
+    if grid.iter().all(|row| row.len() == grid.len()) {
+        Some(grid.len())
+    } else {
+        None
+    }
+}
+
+/// The enhancement rules, keyed by the canonical form of their input so
+/// that a square matches whichever way it is turned.
+struct Rulebook {
+    rules: HashMap<Pattern, Pattern>,
+}
+
+impl Rulebook {
+    /// Builds the rulebook from rules given one per line, or lists everything
+    /// wrong with them: patterns of the wrong size, rules for the same
+    /// pattern up to symmetry, and patterns that no rule matches.
+    fn new(mappings: &[(Grid, Grid)]) -> Result<Rulebook, String> {
+        let mut errors = Vec::new();
+        let mut rules: HashMap<Pattern, (usize, Pattern)> = HashMap::new();
+        for (i, (source, dest)) in mappings.iter().enumerate() {
+            let line = i + 1;
+            let (n, m) = match (square_size(source), square_size(dest)) {
+                (Some(n), Some(m)) => (n, m),
+                _ => {
+                    errors.push(format!("line {}: a pattern is not square", line));
+                    continue;
+                }
+            };
+            if n != 2 && n != 3 {
+                errors.push(format!(
+                    "line {}: the input is {}x{} instead of 2x2 or 3x3",
+                    line, n, n
+                ));
+                continue;
+            }
+            if m != n + 1 {
+                errors.push(format!(
+                    "line {}: the output is {}x{} instead of {}x{}",
+                    line,
+                    m,
+                    m,
+                    n + 1,
+                    n + 1
+                ));
+                continue;
+            }
+            let (source, dest) = (Pattern::new(source).canonical(), Pattern::new(dest));
+            match rules.get(&source) {
+                Some(&(first, d)) if d == dest => errors.push(format!(
+                    "line {}: duplicates the rule on line {}",
+                    line, first
+                )),
+                Some(&(first, _)) => errors.push(format!(
+                    "line {}: conflicts with the rule on line {}",
+                    line, first
+                )),
+                None => {
+                    rules.insert(source, (line, dest));
+                }
+            }
+        }
+
+        for size in 2..=3 {
+            for bits in 0..1 << (size * size) {
+                let pattern = Pattern { size, bits };
+                if pattern.canonical() == pattern && !rules.contains_key(&pattern) {
+                    errors.push(format!("no rule matches {}", pattern));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Rulebook {
+                rules: rules.into_iter().map(|(s, (_, d))| (s, d)).collect(),
+            })
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    fn enhance(&self, pattern: Pattern) -> Pattern {
+        *self
+            .rules
+            .get(&pattern.canonical())
+            .expect("no rule matches a square")
+    }
+}
+
+#[derive(Clone)]
+struct Image {
+    size: usize,
+    /// The pixels row by row from the top left.
+    pixels: Vec<bool>,
+}
+
+impl Image {
+    fn new(pattern: Pattern) -> Image {
+        let size = pattern.size as usize;
+        Image {
+            size,
+            pixels: (0..size * size)
+                .map(|i| pattern.get(i / size, i % size))
+                .collect(),
+        }
+    }
+
+    /// The image with the given pixels, which must make a square that can be
+    /// split into squares of 2 or 3 pixels.
+    fn parse(s: &str) -> Result<Image, String> {
+        let rows = match parse_grid(s) {
+            Ok(("", rows)) => rows,
+            _ => return Err(format!("cannot parse the pattern `{}`", s)),
+        };
+        match square_size(&rows) {
+            Some(size) if size.is_multiple_of(2) || size.is_multiple_of(3) => Ok(Image {
+                size,
+                pixels: rows.into_iter().flatten().collect(),
+            }),
+            _ => Err(format!(
+                "the pattern `{}` is not a square with a side that is a multiple of 2 or 3",
+                s
+            )),
+        }
+    }
+
+    fn lit(&self) -> u64 {
+        self.pixels.iter().filter(|&&on| on).count() as u64
+    }
+
+    /// The square of `n` by `n` pixels with its top left corner at `(row, col)`.
+    fn block(&self, row: usize, col: usize, n: usize) -> Pattern {
+        let mut bits = 0;
+        for r in 0..n {
+            for c in 0..n {
+                bits |= (self.pixels[(row + r) * self.size + col + c] as u16) << (r * n + c);
+            }
+        }
+        Pattern {
+            size: n as u8,
+            bits,
+        }
+    }
+
+    /// The image split into squares of 2 or 3 pixels, row by row.
+    fn blocks(&self) -> Vec<Pattern> {
+        let n = if self.size.is_multiple_of(2) { 2 } else { 3 };
+        let count = self.size / n;
+        (0..count * count)
+            .map(|i| self.block(i / count * n, i % count * n, n))
+            .collect()
+    }
+
+    /// Replaces every square by the output of its rule.
+    fn enhance(&self, rulebook: &Rulebook) -> Image {
+        let blocks = self.blocks();
+        let count = self.size / if self.size.is_multiple_of(2) { 2 } else { 3 };
+        let n = if self.size.is_multiple_of(2) { 3 } else { 4 };
+        let size = count * n;
+        let mut pixels = vec![false; size * size];
+        for (i, block) in blocks.iter().enumerate() {
+            let output = rulebook.enhance(*block);
+            let (row, col) = (i / count * n, i % count * n);
+            for r in 0..n {
+                for c in 0..n {
+                    pixels[(row + r) * size + col + c] = output.get(r, c);
+                }
+            }
+        }
+        Image { size, pixels }
+    }
+}
+
+/// Counts lit pixels without building the image. Once its size is odd, an
+/// image is made of 3x3 squares that each grow into a 9x9 image in three
+/// iterations without affecting each other, so it is enough to know how many
+/// of each square there are.
+struct Expansion<'a> {
+    rulebook: &'a Rulebook,
+    /// The 3x3 squares that every 3x3 square becomes in three iterations.
+    children: HashMap<Pattern, Vec<Pattern>>,
+    /// The number of lit pixels of every 3x3 square after zero, one and two
+    /// iterations.
+    lit: HashMap<Pattern, [u64; 3]>,
+}
+
+impl<'a> Expansion<'a> {
+    fn new(rulebook: &'a Rulebook) -> Expansion<'a> {
+        Expansion {
+            rulebook,
+            children: HashMap::new(),
+            lit: HashMap::new(),
+        }
+    }
+
+    fn lit(&mut self, square: Pattern, iterations: u32) -> u64 {
+        let rulebook = self.rulebook;
+        self.lit.entry(square).or_insert_with(|| {
+            let mut lit = [0; 3];
+            for (i, l) in lit.iter_mut().enumerate() {
+                *l = iterate(i as u32, &Image::new(square), rulebook).lit();
+            }
+            lit
+        })[iterations as usize]
+    }
+
+    fn advance(&mut self, counts: &HashMap<Pattern, BigInt>) -> HashMap<Pattern, BigInt> {
+        let rulebook = self.rulebook;
+        let mut next: HashMap<Pattern, BigInt> = HashMap::new();
+        for (square, count) in counts.iter() {
+            let children = self
+                .children
+                .entry(*square)
+                .or_insert_with(|| iterate(3, &Image::new(*square), rulebook).blocks());
+            for child in children.iter() {
+                *next.entry(*child).or_default() += count;
+            }
+        }
+        next
+    }
+
+    /// The number of lit pixels after every number of iterations from zero
+    /// to `iterations`.
+    fn growth(&mut self, start: &Image, iterations: u32) -> Vec<BigInt> {
+        let mut growth = Vec::new();
+        let mut image = start.clone();
+        while image.size.is_multiple_of(2) {
+            if growth.len() as u32 > iterations {
+                return growth;
+            }
+            growth.push(BigInt::from(image.lit()));
+            image = image.enhance(self.rulebook);
+        }
+
+        let mut counts: HashMap<Pattern, BigInt> = HashMap::new();
+        for square in image.blocks() {
+            *counts.entry(square).or_default() += &BigInt::from(1u64);
+        }
+        for i in 0.. {
+            if growth.len() as u32 > iterations {
+                break;
+            }
+            if i > 0 && i % 3 == 0 {
+                counts = self.advance(&counts);
+            }
+            let mut total = BigInt::default();
+            for (square, count) in counts.iter() {
+                total += &(count * &BigInt::from(self.lit(*square, i % 3)));
+            }
+            growth.push(total);
+        }
+        growth
+    }
+}
+
+fn parse_cell(input: &str) -> IResult<&str, bool> {
+    alt((value(true, char('#')), value(false, char('.'))))(input)
+}
+
+fn parse_grid(input: &str) -> IResult<&str, Grid> {
+    separated_list(char('/'), many1(parse_cell))(input)
+}
+
+fn parse_mapping(input: &str) -> IResult<&str, (Grid, Grid)> {
+    separated_pair(parse_grid, tag(" => "), parse_grid)(input)
+}
+
+fn parse_mappings(input: &str) -> IResult<&str, Vec<(Grid, Grid)>> {
+    separated_list(char('\n'), parse_mapping)(input)
+}
+
+fn iterate(iterations: u32, image: &Image, rulebook: &Rulebook) -> Image {
+    let mut image = image.clone();
+    for _iteration in 0..iterations {
+        image = image.enhance(rulebook);
+    }
+    image
+}
+
+fn parse_rulebook(input: &str) -> Result<Rulebook, String> {
+    match parse_mappings(input.trim_end()) {
+        Ok(("", mappings)) => Rulebook::new(&mappings),
+        Ok((rest, mappings)) => Err(format!(
+            "line {}: cannot parse `{}`",
+            mappings.len() + 1,
+            rest.trim_start().lines().next().unwrap_or("")
+        )),
+        Err(_) => Err(String::from("line 1: cannot parse the rule")),
+    }
+}
+
+/// Checks a rulebook, and returns the number of rules in it.
+pub fn check_rulebook(input: &str) -> Result<usize, String> {
+    parse_rulebook(input).map(|rulebook| rulebook.rules.len())
+}
+
+/// The number of lit pixels after every number of iterations up to
+/// `iterations` from the `start` pattern, one line per iteration.
+pub fn growth_curve(input: &str, start: &str, iterations: u32) -> Result<String, String> {
+    let rulebook = parse_rulebook(input)?;
+    let start = Image::parse(start)?;
+    let mut expansion = Expansion::new(&rulebook);
+    let growth = expansion.growth(&start, iterations);
+    Ok(growth
+        .iter()
+        .enumerate()
+        .map(|(i, lit)| format!("{}\t{}\n", i, lit))
+        .collect())
+}
+
+pub fn solve(input: &str, params: &Parameters) -> Vec<Answer> {
+    let iterations_part1: u32 = params.get("iterations_part1");
+    let iterations_part2: u32 = params.get("iterations_part2");
+
+    let rulebook = parse_rulebook(input).unwrap_or_else(|e| panic!("{}", e));
+    let start = Image::parse(&params.get::<String>("start")).unwrap_or_else(|e| panic!("{}", e));
+
+    let part1 = Answer::new(
+        &format!("Sum after {} iterations", iterations_part1),
+        iterate(iterations_part1, &start, &rulebook).lit(),
+    );
+    end_part();
+
+    let part2 = if params.get("counting") {
+        let mut expansion = Expansion::new(&rulebook);
+        let growth = expansion.growth(&start, iterations_part2);
+        growth[iterations_part2 as usize].to_string()
+    } else {
+        // Once its size is odd, the image is made of 3x3 squares that become
+        // 9x9 images in three iterations independently of each other.
+        let mut image = start;
+        let mut iterations = iterations_part2;
+        while image.size.is_multiple_of(2) && iterations > 0 {
+            image = image.enhance(&rulebook);
+            iterations -= 1;
+        }
+        if image.size.is_multiple_of(2) {
+            image.lit().to_string()
+        } else {
+            let mut cache: HashMap<Pattern, Vec<Pattern>> = HashMap::new();
+            let mut current_set = image.blocks();
+            for _i in 0..iterations / 3 {
+                let mut new_set = Vec::new();
+                for square in current_set.iter() {
+                    let result = cache
+                        .entry(*square)
+                        .or_insert_with(|| iterate(3, &Image::new(*square), &rulebook).blocks());
+                    new_set.extend_from_slice(result);
+                }
+                current_set = new_set;
+            }
+            let remaining_iterations = iterations % 3;
+            current_set
+                .iter()
+                .map(|s| iterate(remaining_iterations, &Image::new(*s), &rulebook).lit())
+                .sum::<u64>()
+                .to_string()
+        }
+    };
+    let part2 = Answer::new(&format!("Sum after {} iterations", iterations_part2), part2);
+
+    vec![part1, part2]
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+use nom::{
+    branch::alt,
+    character::complete::char,
+    combinator::value,
+    multi::{many1, separated_list},
+    IResult,
+};
+
+use crate::solver::{end_part, Answer, Parameter, Parameters};
+
+mod grid;
+pub mod resume;
+
+use grid::{Grid, Nodes};
+
+pub const PARAMETERS: &[Parameter] = &[
+    Parameter {
+        name: "bursts_part1",
+        default: "10000",
+        description: "Number of bursts of activity in part 1",
+    },
+    Parameter {
+        name: "bursts_part2",
+        default: "10000000",
+        description: "Number of bursts of activity in part 2",
+    },
+    Parameter {
+        name: "rule_part1",
+        default: "LR",
+        description: "Turn for every node state in part 1: L, R, N for none or U for a U-turn, each optionally followed by the state it leads to",
+    },
+    Parameter {
+        name: "rule_part2",
+        default: "LNRU",
+        description: "Turn for every node state in part 2: L, R, N for none or U for a U-turn, each optionally followed by the state it leads to",
+    },
+    Parameter {
+        name: "infected_part1",
+        default: "1",
+        description:
+            "Node state that counts as an infection when entered, and for # in the map, in part 1",
+    },
+    Parameter {
+        name: "infected_part2",
+        default: "2",
+        description:
+            "Node state that counts as an infection when entered, and for # in the map, in part 2",
+    },
+    Parameter {
+        name: "direction",
+        default: "N",
+        description: "Direction the carrier starts facing: N, E, S or W",
+    },
+    Parameter {
+        name: "storage",
+        default: "chunks",
+        description: "Where node states are kept: chunks of a dense grid, or a hashmap",
+    },
+];
+
+#[derive(Clone, Copy)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+use Direction::*;
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Direction, String> {
+        match s {
+            "N" => Ok(North),
+            "E" => Ok(East),
+            "S" => Ok(South),
+            "W" => Ok(West),
+            _ => Err(format!("unknown direction: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let letter = match self {
+            North => "N",
+            East => "E",
+            South => "S",
+            West => "W",
+        };
+        write!(f, "{}", letter)
+    }
+}
+
+impl Direction {
+    fn turn_right(&self) -> Direction {
+        match self {
+            North => East,
+            East => South,
+            South => West,
+            West => North,
+        }
+    }
+    fn turn_left(&self) -> Direction {
+        match self {
+            North => West,
+            East => North,
+            South => East,
+            West => South,
+        }
+    }
+    fn reverse(&self) -> Direction {
+        match self {
+            North => South,
+            East => West,
+            South => North,
+            West => East,
+        }
+    }
+    fn turn(&self, turn: Turn) -> Direction {
+        match turn {
+            Turn::Left => self.turn_left(),
+            Turn::Right => self.turn_right(),
+            Turn::None => *self,
+            Turn::Reverse => self.reverse(),
+        }
+    }
+    fn move_forward(&self, coords: &(i32, i32)) -> (i32, i32) {
+        match self {
+            North => (coords.0, coords.1 - 1),
+            East => (coords.0 + 1, coords.1),
+            South => (coords.0, coords.1 + 1),
+            West => (coords.0 - 1, coords.1),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Turn {
+    Left,
+    Right,
+    None,
+    Reverse,
+}
+
+/// A generalized Langton's ant: on a node in state `i`, the carrier turns by
+/// `turns[i]`, moves the node to the next state, wrapping around to state 0
+/// for clean nodes, and moves forward.
+struct Rule {
+    turns: Vec<Turn>,
+    /// The state every state changes to when the carrier leaves it.
+    successors: Vec<u8>,
+    /// The state whose nodes are infected, which they count as when entered.
+    infected: u8,
+}
+
+impl Rule {
+    /// Parses a turn for every state, like `LNRU`, where each state leads to
+    /// the next one and the last one back to the first. A turn can be
+    /// followed by the state it leads to instead, so `L1R0` is the same as
+    /// `LR`, and `L1R1` infects nodes for good.
+    fn new(rule: &str, infected: u8) -> Result<Rule, String> {
+        let mut turns = Vec::new();
+        let mut successors: Vec<Option<usize>> = Vec::new();
+        let mut chars = rule.chars().peekable();
+        while let Some(c) = chars.next() {
+            turns.push(match c {
+                'L' => Turn::Left,
+                'R' => Turn::Right,
+                'N' => Turn::None,
+                'U' => Turn::Reverse,
+                _ => return Err(format!("unknown turn: {}", c)),
+            });
+            let mut digits = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                digits.push(d);
+                chars.next();
+            }
+            successors.push(match digits.as_str() {
+                "" => None,
+                _ => Some(
+                    digits
+                        .parse()
+                        .map_err(|_| format!("state {} does not exist", digits))?,
+                ),
+            });
+        }
+        if turns.len() < 2 || turns.len() > 256 {
+            return Err(format!(
+                "a rule needs between 2 and 256 states, not {}",
+                turns.len()
+            ));
+        }
+        let count = turns.len();
+        for state in successors
+            .iter()
+            .flatten()
+            .chain(Some(&(infected as usize)))
+        {
+            if *state >= count {
+                return Err(format!(
+                    "state {} does not exist in a rule with {} states",
+                    state, count
+                ));
+            }
+        }
+        let successors = successors
+            .iter()
+            .enumerate()
+            .map(|(state, next)| next.unwrap_or((state + 1) % count) as u8)
+            .collect();
+        Ok(Rule {
+            turns,
+            successors,
+            infected,
+        })
+    }
+
+    fn next(&self, state: u8) -> u8 {
+        self.successors[state as usize]
+    }
+}
+
+/// Whether a node of the map is infected.
+fn parse_cell_state(input: &str) -> IResult<&str, bool> {
+    alt((value(true, char('#')), value(false, char('.'))))(input)
+}
+
+fn parse_input(input: &str) -> IResult<&str, Vec<Vec<bool>>> {
+    separated_list(char('\n'), many1(parse_cell_state))(input)
+}
+
+/// The map, with a clear error if it cannot be parsed.
+fn parse_map(input: &str) -> Result<Vec<Vec<bool>>, String> {
+    let input = input.trim_end();
+    if input.is_empty() {
+        return Err(String::from("the map is empty"));
+    }
+    match parse_input(input) {
+        Ok(("", map)) if !map.is_empty() => Ok(map),
+        _ => Err(String::from("cannot parse the map")),
+    }
+}
+
+/// The carrier and the nodes around it, between bursts.
+struct Simulation<N> {
+    nodes: N,
+    position: (i32, i32),
+    direction: Direction,
+    bursts: u64,
+    infections: u64,
+}
+
+impl<N: Nodes> Simulation<N> {
+    /// The carrier in the middle of the map, before the first burst.
+    fn new(mut nodes: N, input: &[Vec<bool>], rule: &Rule, direction: Direction) -> Simulation<N> {
+        for (y, v) in input.iter().enumerate() {
+            for (x, &c) in v.iter().enumerate() {
+                if c {
+                    *nodes.node((x as i32, y as i32)) = rule.infected;
+                }
+            }
+        }
+        let middle = input[0].len() as i32 / 2;
+        Simulation {
+            nodes,
+            position: (middle, middle),
+            direction,
+            bursts: 0,
+            infections: 0,
+        }
+    }
+
+    /// Runs bursts until there have been `until` of them.
+    fn run(&mut self, rule: &Rule, until: u64) {
+        let mut current_coords = self.position;
+        let mut current_dir = self.direction;
+        let mut infections_count = self.infections;
+
+        for _iteration in self.bursts..until {
+            let node = self.nodes.node(current_coords);
+            let state = *node;
+            current_dir = current_dir.turn(rule.turns[state as usize]);
+            *node = rule.next(state);
+            if *node == rule.infected && state != rule.infected {
+                infections_count += 1;
+            }
+            current_coords = current_dir.move_forward(&current_coords);
+        }
+
+        self.position = current_coords;
+        self.direction = current_dir;
+        self.infections = infections_count;
+        self.bursts = self.bursts.max(until);
+    }
+}
+
+/// Runs the bursts, and returns the number of infections and roughly how
+/// many bytes the nodes took.
+fn iterate<N: Nodes>(
+    nodes: N,
+    num_bursts: u64,
+    input: &[Vec<bool>],
+    rule: &Rule,
+    direction: Direction,
+) -> (u64, usize) {
+    let mut simulation = Simulation::new(nodes, input, rule, direction);
+    simulation.run(rule, num_bursts);
+    (simulation.infections, simulation.nodes.memory())
+}
+
+/// Runs the bursts with the nodes in the given storage.
+fn iterate_in(
+    storage: &str,
+    num_bursts: u64,
+    input: &[Vec<bool>],
+    rule: &Rule,
+    direction: Direction,
+) -> Result<(u64, usize), String> {
+    match storage {
+        "chunks" => Ok(iterate(Grid::new(), num_bursts, input, rule, direction)),
+        "hashmap" => Ok(iterate(HashMap::new(), num_bursts, input, rule, direction)),
+        _ => Err(format!("unknown storage: {}", storage)),
+    }
+}
+
+/// Times the bursts of part 2 with every storage, and checks that they count
+/// the same number of infections.
+pub fn benchmark(input: &str, num_bursts: u64) -> Result<String, String> {
+    let input = parse_map(input)?;
+    let rule = Rule::new("LNRU", 2)?;
+
+    let mut output = String::new();
+    let mut results = Vec::new();
+    for storage in ["hashmap", "chunks"].iter() {
+        let start = Instant::now();
+        let (infections, memory) = iterate_in(storage, num_bursts, &input, &rule, North)?;
+        let elapsed = start.elapsed().as_secs_f64();
+        output.push_str(&format!(
+            "{:<8} {} infections in {:.3} s, {:.1} MB",
+            storage,
+            infections,
+            elapsed,
+            memory as f64 / 1e6
+        ));
+        if let Some(&(_, first)) = results.first() {
+            output.push_str(&format!(", {:.1}x as fast", first / elapsed));
+        }
+        output.push('\n');
+        results.push((infections, elapsed));
+    }
+    if results
+        .iter()
+        .any(|&(infections, _)| infections != results[0].0)
+    {
+        return Err(String::from("the storages count different infections"));
+    }
+    Ok(output)
+}
+
+pub fn solve(input: &str, params: &Parameters) -> Vec<Answer> {
+    let bursts_part1: u64 = params.get("bursts_part1");
+    let bursts_part2: u64 = params.get("bursts_part2");
+
+    let rule_part1 = Rule::new(
+        &params.get::<String>("rule_part1"),
+        params.get("infected_part1"),
+    )
+    .unwrap_or_else(|e| panic!("{}", e));
+    let rule_part2 = Rule::new(
+        &params.get::<String>("rule_part2"),
+        params.get("infected_part2"),
+    )
+    .unwrap_or_else(|e| panic!("{}", e));
+    let direction: Direction = params.get("direction");
+    let storage: String = params.get("storage");
+
+    let input = parse_map(input).unwrap_or_else(|e| panic!("{}", e));
+
+    let part1 = Answer::new(
+        &format!("Number of infections after {} iterations", bursts_part1),
+        iterate_in(&storage, bursts_part1, &input, &rule_part1, direction)
+            .unwrap()
+            .0,
+    );
+    end_part();
+
+    vec![
+        part1,
+        Answer::new(
+            &format!("Number of infections after {} iterations", bursts_part2),
+            iterate_in(&storage, bursts_part2, &input, &rule_part2, direction)
+                .unwrap()
+                .0,
+        ),
+    ]
+}
@@ -0,0 +1,105 @@
+//! An unbounded grid of node states, one byte per node, stored in square
+//! chunks that are allocated when a node in them is first touched.
+
+use std::collections::HashMap;
+use std::mem;
+
+const CHUNK_BITS: i32 = 6;
+const CHUNK_SIZE: i32 = 1 << CHUNK_BITS;
+const CHUNK_MASK: i32 = CHUNK_SIZE - 1;
+const CHUNK_NODES: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+type Chunk = Box<[u8; CHUNK_NODES]>;
+
+/// Where the states of the nodes are kept. Nodes are in state 0 until they
+/// are written.
+pub trait Nodes {
+    fn node(&mut self, coords: (i32, i32)) -> &mut u8;
+    /// Roughly how many bytes the nodes take.
+    fn memory(&self) -> usize;
+}
+
+impl Nodes for HashMap<(i32, i32), u8> {
+    fn node(&mut self, coords: (i32, i32)) -> &mut u8 {
+        self.entry(coords).or_insert(0)
+    }
+
+    fn memory(&self) -> usize {
+        // Every slot holds an entry and a control byte.
+        self.capacity() * (mem::size_of::<((i32, i32), u8)>() + 1)
+    }
+}
+
+/// The chunks are found by their chunk coordinates, so only the chunks that
+/// were touched take memory however far apart they are.
+pub struct Grid {
+    chunks: Vec<Chunk>,
+    /// The index in `chunks` of every chunk, by chunk coordinates.
+    index: HashMap<(i32, i32), usize>,
+    /// The chunk coordinates and index of the chunk used last, which is
+    /// almost always the next one used.
+    last: Option<((i32, i32), usize)>,
+}
+
+impl Grid {
+    pub fn new() -> Grid {
+        Grid {
+            chunks: Vec::new(),
+            index: HashMap::new(),
+            last: None,
+        }
+    }
+
+    /// The index of chunk `coords`, which is allocated if it was not yet.
+    fn chunk(&mut self, coords: (i32, i32)) -> usize {
+        match self.last {
+            Some((last, i)) if last == coords => i,
+            _ => {
+                let chunks = &mut self.chunks;
+                let i = *self.index.entry(coords).or_insert_with(|| {
+                    chunks.push(Box::new([0; CHUNK_NODES]));
+                    chunks.len() - 1
+                });
+                self.last = Some((coords, i));
+                i
+            }
+        }
+    }
+
+    /// The chunks with a node that is not clean, by chunk coordinates, with
+    /// their nodes row by row.
+    pub fn chunks(&self) -> Vec<((i32, i32), &[u8])> {
+        let mut chunks: Vec<((i32, i32), &[u8])> = self
+            .index
+            .iter()
+            .map(|(&coords, &i)| (coords, &self.chunks[i][..]))
+            .filter(|(_, nodes)| nodes.iter().any(|&node| node != 0))
+            .collect();
+        chunks.sort_by_key(|&((cx, cy), _)| (cy, cx));
+        chunks
+    }
+
+    /// Sets the nodes of a chunk, or returns `None` if there are not as many
+    /// as in a chunk.
+    pub fn set_chunk(&mut self, coords: (i32, i32), nodes: &[u8]) -> Option<()> {
+        if nodes.len() != CHUNK_NODES {
+            return None;
+        }
+        let i = self.chunk(coords);
+        self.chunks[i].copy_from_slice(nodes);
+        Some(())
+    }
+}
+
+impl Nodes for Grid {
+    fn node(&mut self, (x, y): (i32, i32)) -> &mut u8 {
+        let i = self.chunk((x >> CHUNK_BITS, y >> CHUNK_BITS));
+        &mut self.chunks[i][((y & CHUNK_MASK) << CHUNK_BITS | x & CHUNK_MASK) as usize]
+    }
+
+    fn memory(&self) -> usize {
+        self.chunks.capacity() * mem::size_of::<Chunk>()
+            + self.chunks.len() * CHUNK_NODES
+            + self.index.capacity() * (mem::size_of::<((i32, i32), usize)>() + 1)
+    }
+}
@@ -0,0 +1,224 @@
+//! Long runs of the virus that save checkpoints as they go, and can resume
+//! from one of them after being stopped.
+//!
+//! After its header fields, a checkpoint has a line `cx cy NODES` for every
+//! chunk with a node that is not clean, with the chunk coordinates and the
+//! node states in hexadecimal, row by row.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::grid::Grid;
+use super::{parse_map, Direction, Rule, Simulation};
+use crate::cache;
+use crate::checkpoint::{self, Checkpoint, Schedule};
+
+const KIND: &str = "day22-checkpoint";
+const VERSION: u32 = 1;
+
+pub struct Options {
+    /// The turn for every node state, as in the `rule_part2` parameter.
+    pub rule: String,
+    pub infected: u8,
+    pub direction: String,
+    pub schedule: Schedule,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            rule: String::from("LNRU"),
+            infected: 2,
+            direction: String::from("N"),
+            schedule: Schedule::new("day22.checkpoint"),
+        }
+    }
+}
+
+fn to_checkpoint(simulation: &Simulation<Grid>, input: &str, options: &Options) -> Checkpoint {
+    let mut checkpoint = Checkpoint::new();
+    checkpoint.set("input", cache::hash_input(input));
+    checkpoint.set("rule", &options.rule);
+    checkpoint.set("infected", options.infected);
+    checkpoint.set("bursts", simulation.bursts);
+    checkpoint.set("infections", simulation.infections);
+    checkpoint.set("x", simulation.position.0);
+    checkpoint.set("y", simulation.position.1);
+    checkpoint.set("direction", simulation.direction);
+    checkpoint.lines = simulation
+        .nodes
+        .chunks()
+        .into_iter()
+        .map(|((cx, cy), nodes)| format!("{} {} {}", cx, cy, checkpoint::to_hex(nodes)))
+        .collect();
+    checkpoint
+}
+
+fn save(
+    simulation: &Simulation<Grid>,
+    input: &str,
+    options: &Options,
+    path: &Path,
+) -> Result<(), String> {
+    to_checkpoint(simulation, input, options)
+        .save(path, KIND, VERSION)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The simulation saved in the checkpoint at `path`, which must have been
+/// made from the same input and rule.
+fn load(path: &Path, input: &str, options: &Options) -> Result<Simulation<Grid>, String> {
+    let error = |e: String| format!("{}: {}", path.display(), e);
+    let checkpoint = Checkpoint::load(path, KIND, VERSION).map_err(|e| error(e.to_string()))?;
+    let field = |name: &str| {
+        checkpoint
+            .get::<String>(name)
+            .map_err(|e| error(e.to_string()))
+    };
+    if field("input")? != cache::hash_input(input) {
+        return Err(error(String::from("made from a different input")));
+    }
+    let (rule, infected) = (field("rule")?, field("infected")?);
+    if rule != options.rule || infected != options.infected.to_string() {
+        return Err(error(format!(
+            "made with rule {} and infected state {}, not {} and {}",
+            rule, infected, options.rule, options.infected
+        )));
+    }
+
+    let mut nodes = Grid::new();
+    for (i, line) in checkpoint.lines.iter().enumerate() {
+        let mut parts = line.split(' ');
+        let chunk = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(cx), Some(cy), Some(hex), None) => (|| {
+                let coords = (cx.parse().ok()?, cy.parse().ok()?);
+                nodes.set_chunk(coords, &checkpoint::from_hex(hex)?)
+            })(),
+            _ => None,
+        };
+        if chunk.is_none() {
+            return Err(error(format!("invalid chunk on line {}", i + 2)));
+        }
+    }
+
+    let get = |e: io::Error| error(e.to_string());
+    Ok(Simulation {
+        nodes,
+        position: (
+            checkpoint.get("x").map_err(get)?,
+            checkpoint.get("y").map_err(get)?,
+        ),
+        direction: checkpoint.get("direction").map_err(get)?,
+        bursts: checkpoint.get("bursts").map_err(get)?,
+        infections: checkpoint.get("infections").map_err(get)?,
+    })
+}
+
+/// Runs `bursts` bursts in all, starting from a checkpoint if the options
+/// name one, and saving checkpoints on their schedule.
+pub fn run(input: &str, bursts: u64, options: &Options) -> Result<String, String> {
+    let map = parse_map(input)?;
+    let rule = Rule::new(&options.rule, options.infected)?;
+    let direction: Direction = options.direction.parse()?;
+    let schedule = &options.schedule;
+
+    let mut output = String::new();
+    let mut simulation = match &schedule.resume {
+        Some(path) => {
+            let simulation = load(path, input, options)?;
+            output.push_str(&format!(
+                "Resumed from {} after {} bursts\n",
+                path.display(),
+                simulation.bursts
+            ));
+            simulation
+        }
+        None => Simulation::new(Grid::new(), &map, &rule, direction),
+    };
+    if simulation.bursts > bursts {
+        return Err(format!("the checkpoint is already past {} bursts", bursts));
+    }
+
+    let mut saved = None;
+    while simulation.bursts < bursts {
+        let stop = schedule.next_stop(simulation.bursts, bursts);
+        simulation.run(&rule, stop);
+        if schedule.is_due(stop) {
+            save(&simulation, input, options, &schedule.path)?;
+            saved = Some(stop);
+        }
+    }
+    if let Some(stop) = saved {
+        output.push_str(&format!(
+            "Saved the last checkpoint to {} after {} bursts\n",
+            schedule.path.display(),
+            stop
+        ));
+    }
+    output.push_str(&format!(
+        "Number of infections after {} bursts: {}\n",
+        bursts, simulation.infections
+    ));
+    Ok(output)
+}
+
+/// Runs `bursts` bursts straight, and again stopping after a third and two
+/// thirds of them to save a checkpoint and resume from it, and checks that
+/// both runs end in the same state.
+pub fn check(input: &str, bursts: u64, options: &Options) -> Result<String, String> {
+    let map = parse_map(input)?;
+    let rule = Rule::new(&options.rule, options.infected)?;
+    let direction: Direction = options.direction.parse()?;
+
+    let mut straight = Simulation::new(Grid::new(), &map, &rule, direction);
+    straight.run(&rule, bursts);
+
+    let path = checkpoint::temp_path(KIND);
+    let mut resumed = Simulation::new(Grid::new(), &map, &rule, direction);
+    for &stop in [bursts / 3, bursts / 3 * 2].iter() {
+        resumed.run(&rule, stop);
+        save(&resumed, input, options, &path)?;
+        resumed = load(&path, input, options)?;
+    }
+    fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    resumed.run(&rule, bursts);
+
+    let expected = to_checkpoint(&straight, input, options).contents(KIND, VERSION);
+    let actual = to_checkpoint(&resumed, input, options).contents(KIND, VERSION);
+    if actual != expected {
+        return Err(format!(
+            "resuming from checkpoints after {} and {} bursts changes the state after {}",
+            bursts / 3,
+            bursts / 3 * 2,
+            bursts
+        ));
+    }
+    Ok(format!(
+        "Resuming from checkpoints after {} and {} bursts gives the same state as a straight run: {} infections after {} bursts\n",
+        bursts / 3,
+        bursts / 3 * 2,
+        resumed.infections,
+        bursts
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One test, as every check saves its checkpoints to the same file.
+    #[test]
+    fn resuming_matches_a_straight_run() {
+        let input = fs::read_to_string("data/day22.txt").unwrap();
+        let output = check(&input, 100000, &Options::default()).unwrap();
+        assert!(output.contains("25226 infections after 100000 bursts"));
+
+        let options = Options {
+            rule: String::from("R1L2U0"),
+            infected: 1,
+            ..Options::default()
+        };
+        check(&input, 100000, &options).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+use crate::duet::{
+    self,
+    bytecode::{self, Arithmetic, Event, Machine},
+    Register,
+};
+use crate::solver::{end_part, Answer, Parameter, Parameters};
+
+pub mod analysis;
+mod optimizer;
+
+pub const PARAMETERS: &[Parameter] = &[Parameter {
+    name: "arithmetic",
+    default: "checked",
+    description:
+        "What happens when a register overflows: checked stops the program, wrapping or saturating",
+}];
+
+fn register(name: &str) -> Register {
+    Register::new(name).unwrap()
+}
+
+/// Panics unless the machine stopped because the program halted, as the
+/// coprocessor has nothing to send to or receive from.
+fn expect_halt(machine: &Machine, event: Event) {
+    match event {
+        Event::Halted => {}
+        Event::Fault(fault) => panic!("{} at instruction {}", fault, machine.pc()),
+        Event::Sent(_) | Event::Receiving(_) => {
+            panic!("the coprocessor cannot send or receive")
+        }
+    }
+}
+
+pub fn solve(input: &str, params: &Parameters) -> Vec<Answer> {
+    let mut answers = Vec::new();
+    let arithmetic: Arithmetic = params.get("arithmetic");
+
+    let instructions = duet::parse(input).unwrap_or_else(|e| panic!("{}", e));
+    let program = bytecode::Program::compile(&instructions);
+    let mut machine = Machine::with_arithmetic(&program, arithmetic);
+    let event = machine.run();
+    expect_halt(&machine, event);
+    answers.push(Answer::new(
+        "The number of times the mul instruction is called in debug mode",
+        machine.muls,
+    ));
+    end_part();
+
+    let (a, h) = (register("a"), register("h"));
+    let h = match analysis::closed_form(&instructions, &[(a, 1)]) {
+        Some(form) => form.evaluate(),
+        None => {
+            let optimized = optimizer::optimize(&instructions);
+            let mut machine = Machine::with_arithmetic(&program, arithmetic);
+            machine.set(a, 1);
+            let event = optimized.run(&mut machine);
+            expect_halt(&machine, event);
+            machine.get(h)
+        }
+    };
+    answers.push(Answer::new(
+        "The value of the h register after the program ends",
+        h,
+    ));
+
+    answers
+}
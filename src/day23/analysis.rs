@@ -0,0 +1,648 @@
+//! Static analysis of coprocessor programs: what every register holds before
+//! each instruction, which registers are live there, and a closed form for
+//! the loop that counts composite numbers.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::optimizer::{self, Idiom, Optimized};
+use super::register;
+use crate::duet::{self, Condition, Instruction, Number, Register, Value};
+
+/// The register the puzzle asks for, which is the only one live when the
+/// program halts.
+const OUTPUT: &str = "h";
+
+/// What a register holds at some point of the program, in terms of the
+/// values the registers had when it started.
+#[derive(Clone, PartialEq)]
+enum Expr {
+    Constant(Number),
+    /// The value a register had at the start of the program.
+    Initial(Register),
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Mod(Box<Expr>, Box<Expr>),
+    /// Different values on different paths, such as a loop counter.
+    Unknown,
+}
+
+impl Expr {
+    fn add(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Unknown, _) | (_, Expr::Unknown) => Expr::Unknown,
+            (Expr::Constant(x), Expr::Constant(y)) => {
+                x.checked_add(y).map_or(Expr::Unknown, Expr::Constant)
+            }
+            (x, Expr::Constant(0)) | (Expr::Constant(0), x) => x,
+            (Expr::Add(x, c), Expr::Constant(y)) => match *c {
+                Expr::Constant(c) => Expr::add(*x, Expr::add(Expr::Constant(c), Expr::Constant(y))),
+                c => Expr::Add(
+                    Box::new(Expr::Add(x, Box::new(c))),
+                    Box::new(Expr::Constant(y)),
+                ),
+            },
+            // Constants go last, so that they can be folded together.
+            (Expr::Constant(x), y) => Expr::add(y, Expr::Constant(x)),
+            (x, y) => Expr::Add(Box::new(x), Box::new(y)),
+        }
+    }
+
+    fn sub(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Unknown, _) | (_, Expr::Unknown) => Expr::Unknown,
+            (x, y) if x == y => Expr::Constant(0),
+            (x, Expr::Constant(y)) => match y.checked_neg() {
+                Some(y) => Expr::add(x, Expr::Constant(y)),
+                None => Expr::Unknown,
+            },
+            (x, y) => Expr::add(x, Expr::mul(y, Expr::Constant(-1))),
+        }
+    }
+
+    fn mul(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Constant(0), _) | (_, Expr::Constant(0)) => Expr::Constant(0),
+            (Expr::Unknown, _) | (_, Expr::Unknown) => Expr::Unknown,
+            (Expr::Constant(x), Expr::Constant(y)) => {
+                x.checked_mul(y).map_or(Expr::Unknown, Expr::Constant)
+            }
+            (x, Expr::Constant(1)) | (Expr::Constant(1), x) => x,
+            (Expr::Constant(x), y) => Expr::mul(y, Expr::Constant(x)),
+            (x, y) => Expr::Mul(Box::new(x), Box::new(y)),
+        }
+    }
+
+    fn modulo(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            // The program would crash, so nothing after it is reached this way.
+            (_, Expr::Constant(0)) => Expr::Unknown,
+            (Expr::Unknown, _) | (_, Expr::Unknown) => Expr::Unknown,
+            (Expr::Constant(x), Expr::Constant(y)) => {
+                x.checked_rem(y).map_or(Expr::Unknown, Expr::Constant)
+            }
+            (x, y) => Expr::Mod(Box::new(x), Box::new(y)),
+        }
+    }
+
+    fn join(&self, other: &Expr) -> Expr {
+        if self == other {
+            self.clone()
+        } else {
+            Expr::Unknown
+        }
+    }
+
+    /// The expression as the operand of another one.
+    fn operand(&self) -> String {
+        match self {
+            Expr::Add(..) | Expr::Mul(..) | Expr::Mod(..) => format!("({})", self),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Constant(n) => write!(f, "{}", n),
+            Expr::Initial(r) => write!(f, "{}0", r),
+            Expr::Add(x, y) => match &**y {
+                Expr::Constant(n) if *n < 0 => {
+                    write!(f, "{} - {}", x, n.unsigned_abs())
+                }
+                Expr::Mul(y, minus) if **minus == Expr::Constant(-1) => {
+                    write!(f, "{} - {}", x, y.operand())
+                }
+                _ => write!(f, "{} + {}", x, y.operand()),
+            },
+            Expr::Mul(x, y) => write!(f, "{} * {}", x.operand(), y.operand()),
+            Expr::Mod(x, y) => write!(f, "{} % {}", x.operand(), y.operand()),
+            Expr::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+/// The contents of the registers. Registers that are left out hold zero, so
+/// that equal states are equal maps.
+type State = BTreeMap<Register, Expr>;
+
+fn get(state: &State, r: Register) -> Expr {
+    state.get(&r).cloned().unwrap_or(Expr::Constant(0))
+}
+
+fn set(state: &mut State, r: Register, e: Expr) {
+    if e == Expr::Constant(0) {
+        state.remove(&r);
+    } else {
+        state.insert(r, e);
+    }
+}
+
+fn evaluate(value: &Value, state: &State) -> Expr {
+    match value {
+        Value::Register(r) => get(state, *r),
+        Value::Literal(n) => Expr::Constant(*n),
+    }
+}
+
+fn transfer(instruction: &Instruction, state: &State) -> State {
+    let mut next = state.clone();
+    match instruction {
+        Instruction::Set(r, v) => set(&mut next, *r, evaluate(v, state)),
+        Instruction::Add(r, v) => set(&mut next, *r, Expr::add(get(state, *r), evaluate(v, state))),
+        Instruction::Sub(r, v) => set(&mut next, *r, Expr::sub(get(state, *r), evaluate(v, state))),
+        Instruction::Mul(r, v) => set(&mut next, *r, Expr::mul(get(state, *r), evaluate(v, state))),
+        Instruction::Mod(r, v) => set(
+            &mut next,
+            *r,
+            Expr::modulo(get(state, *r), evaluate(v, state)),
+        ),
+        // Whatever is received comes from outside the program.
+        Instruction::Rcv(r) => set(&mut next, *r, Expr::Unknown),
+        Instruction::Snd(_) | Instruction::Jgz(..) | Instruction::Jnz(..) => {}
+    }
+    next
+}
+
+/// Whether a jump is taken when its condition register holds `value`, if
+/// that can be told.
+fn taken(condition: Condition, value: &Expr) -> Option<bool> {
+    match (condition, value) {
+        (Condition::Always, _) => Some(true),
+        (Condition::Never, _) => Some(false),
+        (Condition::Positive(_), Expr::Constant(n)) => Some(*n > 0),
+        (Condition::NonZero(_), Expr::Constant(n)) => Some(*n != 0),
+        _ => None,
+    }
+}
+
+/// The instructions that can follow instruction `i`. The state decides the
+/// branches whose condition is known, and `None` lets every branch be taken.
+/// Jumps out of the program halt it and are left out.
+fn successors(code: &[Instruction], i: usize, state: Option<&State>) -> Vec<usize> {
+    let (condition, offset) = match code[i].jump() {
+        Some(jump) => jump,
+        None => {
+            return if i + 1 < code.len() {
+                vec![i + 1]
+            } else {
+                vec![]
+            }
+        }
+    };
+
+    let (value, offset) = match state {
+        Some(state) => (
+            match condition {
+                Condition::Positive(r) | Condition::NonZero(r) => get(state, r),
+                _ => Expr::Unknown,
+            },
+            evaluate(offset, state),
+        ),
+        None => (
+            Expr::Unknown,
+            match offset {
+                Value::Literal(n) => Expr::Constant(*n),
+                _ => Expr::Unknown,
+            },
+        ),
+    };
+    let taken = taken(condition, &value);
+    let mut next = Vec::new();
+    if taken != Some(true) {
+        next.push(i + 1);
+    }
+    if taken != Some(false) {
+        match offset {
+            Expr::Constant(n) => {
+                let t = i as Number + n;
+                if t >= 0 && (t as usize) < code.len() {
+                    next.push(t as usize);
+                }
+            }
+            _ => return (0..code.len()).collect(),
+        }
+    }
+    next.retain(|&t| t < code.len());
+    next
+}
+
+/// The registers an instruction reads and the one it writes.
+fn uses_and_definition(instruction: &Instruction) -> (Vec<Register>, Option<Register>) {
+    let register = |v: &Value| match v {
+        Value::Register(r) => Some(*r),
+        Value::Literal(_) => None,
+    };
+    match instruction {
+        Instruction::Set(r, v) => (register(v).into_iter().collect(), Some(*r)),
+        Instruction::Add(r, v)
+        | Instruction::Sub(r, v)
+        | Instruction::Mul(r, v)
+        | Instruction::Mod(r, v) => {
+            let mut uses = vec![*r];
+            uses.extend(register(v));
+            (uses, Some(*r))
+        }
+        Instruction::Snd(v) => (register(v).into_iter().collect(), None),
+        Instruction::Rcv(r) => (vec![], Some(*r)),
+        Instruction::Jgz(v1, v2) | Instruction::Jnz(v1, v2) => {
+            (register(v1).into_iter().chain(register(v2)).collect(), None)
+        }
+    }
+}
+
+struct Analysis {
+    /// What the registers hold before each instruction, or `None` if it is
+    /// never reached.
+    before: Vec<Option<State>>,
+    /// The registers whose value is read later, before each instruction.
+    live: Vec<BTreeSet<Register>>,
+}
+
+/// The registers when the program starts: the given ones, and zero for the
+/// others.
+fn start_state(initial: &[(Register, Expr)]) -> State {
+    let mut state = State::new();
+    for (r, e) in initial.iter() {
+        set(&mut state, *r, e.clone());
+    }
+    state
+}
+
+fn join(a: &State, b: &State) -> State {
+    let mut joined = State::new();
+    for &r in a.keys().chain(b.keys()) {
+        set(&mut joined, r, get(a, r).join(&get(b, r)));
+    }
+    joined
+}
+
+/// Propagates the start state through the program until nothing changes. A
+/// register that gets different values on different paths becomes unknown,
+/// so every loop that changes it settles after going round once.
+fn propagate(code: &[Instruction], start: &State) -> Vec<Option<State>> {
+    let mut before: Vec<Option<State>> = vec![None; code.len()];
+    if code.is_empty() {
+        return before;
+    }
+    before[0] = Some(start.clone());
+
+    let mut pending = vec![0];
+    while let Some(i) = pending.pop() {
+        let after = transfer(&code[i], before[i].as_ref().unwrap());
+        for s in successors(code, i, Some(&after)) {
+            let merged = match &before[s] {
+                None => after.clone(),
+                Some(old) => join(old, &after),
+            };
+            if before[s].as_ref() != Some(&merged) {
+                before[s] = Some(merged);
+                pending.push(s);
+            }
+        }
+    }
+    before
+}
+
+fn liveness(code: &[Instruction]) -> Vec<BTreeSet<Register>> {
+    let mut live = vec![BTreeSet::new(); code.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in (0..code.len()).rev() {
+            let mut out: BTreeSet<Register> = successors(code, i, None)
+                .into_iter()
+                .flat_map(|s| live[s].clone())
+                .collect();
+            if can_halt(code, i) {
+                out.insert(register(OUTPUT));
+            }
+
+            let (uses, definition) = uses_and_definition(&code[i]);
+            if let Some(r) = definition {
+                out.remove(&r);
+            }
+            out.extend(uses);
+            if out != live[i] {
+                live[i] = out;
+                changed = true;
+            }
+        }
+    }
+    live
+}
+
+/// Whether the program can halt right after instruction `i`.
+fn can_halt(code: &[Instruction], i: usize) -> bool {
+    let last = i + 1 == code.len();
+    match code[i].jump() {
+        Some((condition, offset)) => {
+            let (jumps, falls) = match condition {
+                Condition::Never => (false, true),
+                Condition::Always => (true, false),
+                Condition::Positive(_) | Condition::NonZero(_) => (true, true),
+            };
+            let out = match offset {
+                Value::Literal(n) => {
+                    let t = i as Number + n;
+                    t < 0 || t as usize >= code.len()
+                }
+                Value::Register(_) => true,
+            };
+            jumps && out || falls && last
+        }
+        None => last,
+    }
+}
+
+fn analyze(code: &[Instruction], start: &State) -> Analysis {
+    Analysis {
+        before: propagate(code, start),
+        live: liveness(code),
+    }
+}
+
+/// What a register counts when the program runs
+///
+/// ```text
+/// do { flag = 1; FactorSearch(x from X, y from Y, z = b); if flag == 0 { h += 1 }; if b == c { halt }; b += step }
+/// ```
+///
+/// starting from the given values of `b`, `c` and `h`.
+pub(super) struct ClosedForm {
+    counter: Register,
+    initial: Number,
+    start: Number,
+    end: Number,
+    step: Number,
+    x_start: Number,
+    y_start: Number,
+}
+
+/// Whether `n` is a product `x * y` with `x` in `x_start..n` and `y` in
+/// `y_start..n`, for positive starts.
+fn factorable(n: Number, x_start: Number, y_start: Number) -> bool {
+    let fits = |x: Number, y: Number| x >= x_start && x < n && y >= y_start && y < n;
+    (1..)
+        .take_while(|d| d * d <= n)
+        .filter(|d| n % d == 0)
+        .any(|d| fits(d, n / d) || fits(n / d, d))
+}
+
+impl ClosedForm {
+    pub(super) fn evaluate(&self) -> Number {
+        let count = (0..=(self.end - self.start) / self.step)
+            .map(|k| self.start + k * self.step)
+            .filter(|&n| factorable(n, self.x_start, self.y_start))
+            .count();
+        self.initial + count as Number
+    }
+}
+
+impl fmt::Display for ClosedForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {} + the number of ", self.counter, self.initial)?;
+        if self.x_start <= 2 && self.y_start <= 2 {
+            write!(f, "composite numbers")?;
+        } else {
+            write!(
+                f,
+                "products x * y with x >= {} and y >= {}",
+                self.x_start, self.y_start
+            )?;
+        }
+        write!(f, " in {}..={} step {}", self.start, self.end, self.step)
+    }
+}
+
+/// The number of instructions in the counting loop.
+const COUNTER_LENGTH: usize = 24;
+
+/// Matches the counting loop at `start` in the optimized code, with the
+/// factor search already fused, and returns its registers along with the
+/// start of `x` and `y`:
+///
+/// ```text
+/// set F 1; set X S; <FactorSearch>; jnz F 2; sub H -1; set T B; sub T C; jnz T 2; jnz 1 <out>; sub B -STEP; jnz 1 -23
+/// ```
+fn match_counter(
+    optimized: &Optimized,
+    start: usize,
+) -> Option<(Register, Register, Value, Number, Number, Number)> {
+    use Instruction::{Jnz, Set, Sub};
+    use Value::{Literal, Register as Reg};
+
+    let code = &optimized.code;
+    let search = match optimized.fused.get(start + 2)? {
+        Some(search) if search.end == start + 16 => search,
+        _ => return None,
+    };
+    let (x, y, y_start, b, flag, temp) = match search.idiom {
+        Idiom::FactorSearch {
+            x,
+            y,
+            start: Literal(y_start),
+            z: Reg(b),
+            flag,
+            temp,
+        } => (x, y, y_start, b, flag, temp),
+        _ => return None,
+    };
+
+    match code.get(start..start + COUNTER_LENGTH)? {
+        [Set(f0, Literal(1)), Set(x0, Literal(x_start)), .., Jnz(Reg(f1), Literal(2)), Sub(h, Literal(-1)), Set(t0, Reg(b0)), Sub(t1, c), Jnz(Reg(t2), Literal(2)), Jnz(Literal(out), Literal(exit)), Sub(b1, Literal(step)), Jnz(Literal(back), Literal(-23))]
+            if *f0 == flag
+                && *f1 == flag
+                && *x0 == x
+                && *b0 == b
+                && *b1 == b
+                && t0 == t1
+                && t0 == t2
+                && *out != 0
+                && *back != 0
+                && *step != 0
+                && {
+                    let t = (start + 21) as Number + exit;
+                    t < 0 || t as usize >= code.len()
+                } =>
+        {
+            let written = [flag, x, y, temp, *h, b];
+            let distinct = written
+                .iter()
+                .enumerate()
+                .all(|(i, r)| !written[i + 1..].contains(r));
+            let c_fixed = match c {
+                Reg(c) => !written.contains(c) && c != t0,
+                Literal(_) => true,
+            };
+            if distinct && c_fixed && ![flag, x, y, *h, b].contains(t0) {
+                Some((*h, b, c.clone(), -step, *x_start, y_start))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The start and closed form of the counting loop, if the program has one
+/// and the analysis knows the registers it is entered with.
+fn find_closed_form(
+    code: &[Instruction],
+    start_state: &State,
+    before: &[Option<State>],
+) -> Option<(usize, ClosedForm)> {
+    let optimized = optimizer::optimize(code);
+    (0..code.len()).find_map(|start| {
+        let (h, b, c, step, x_start, y_start) = match_counter(&optimized, start)?;
+        let end = start + COUNTER_LENGTH;
+
+        // The loop has to be entered at the top, from instructions the
+        // analysis can say something about.
+        let mut entry = if start == 0 {
+            Some(start_state.clone())
+        } else {
+            None
+        };
+        for (p, state) in before.iter().enumerate() {
+            let state = match state {
+                Some(state) if p < start || p >= end => state,
+                _ => continue,
+            };
+            let after = transfer(&code[p], state);
+            let next = successors(code, p, Some(&after));
+            if next.iter().any(|&s| s > start && s < end) {
+                return None;
+            }
+            if next.contains(&start) {
+                entry = Some(match entry {
+                    None => after,
+                    Some(e) => join(&e, &after),
+                });
+            }
+        }
+        let entry = entry?;
+
+        let constant = |e: &Expr| match e {
+            Expr::Constant(n) => Some(*n),
+            _ => None,
+        };
+        let form = ClosedForm {
+            counter: h,
+            initial: constant(&get(&entry, h))?,
+            start: constant(&get(&entry, b))?,
+            end: constant(&evaluate(&c, &entry))?,
+            step,
+            x_start,
+            y_start,
+        };
+
+        // Otherwise the loop never ends, or the factor search does not apply.
+        let distance = form.end - form.start;
+        let terminates = distance % step == 0 && distance / step >= 0;
+        let smallest = form.start.min(form.end);
+        if terminates && x_start >= 1 && y_start >= 1 && smallest > x_start.max(y_start) {
+            Some((start, form))
+        } else {
+            None
+        }
+    })
+}
+
+/// The closed form of the program with the given registers set, if it is the
+/// composite counting loop.
+pub(super) fn closed_form(
+    code: &[Instruction],
+    initial: &[(Register, Number)],
+) -> Option<ClosedForm> {
+    let initial: Vec<(Register, Expr)> = initial
+        .iter()
+        .map(|&(r, n)| (r, Expr::Constant(n)))
+        .collect();
+    let start = start_state(&initial);
+    let before = propagate(code, &start);
+    find_closed_form(code, &start, &before).map(|(_, form)| form)
+}
+
+/// Analyzes a program and describes, for every instruction, the registers
+/// live before it and what the analysis knows about the others. Each
+/// assignment gives a register a start value, or makes it symbolic if the
+/// value is `None`; the other registers start at zero.
+pub fn report(input: &str, assignments: &[(Register, Option<i64>)]) -> Result<String, String> {
+    let code = duet::parse(input)?;
+    let mut registers = BTreeSet::new();
+    for instruction in code.iter() {
+        let (uses, definition) = uses_and_definition(instruction);
+        registers.extend(uses.into_iter().chain(definition));
+    }
+    let mut initial = Vec::new();
+    for &(r, value) in assignments.iter() {
+        if !registers.contains(&r) {
+            return Err(format!("unknown register: {}", r));
+        }
+        initial.push((r, value.map_or(Expr::Initial(r), Expr::Constant)));
+    }
+
+    let start = start_state(&initial);
+    let analysis = analyze(&code, &start);
+    let rows: Vec<(String, String, String)> = code
+        .iter()
+        .enumerate()
+        .map(|(i, instruction)| {
+            let live: Vec<&str> = analysis.live[i].iter().map(|r| r.as_str()).collect();
+            let known = match &analysis.before[i] {
+                None => String::from("unreachable"),
+                Some(state) => registers
+                    .iter()
+                    .map(|&r| (r, get(state, r)))
+                    .filter(|(_, e)| *e != Expr::Unknown)
+                    .map(|(r, e)| format!("{}={}", r, e))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            };
+            (instruction.to_string(), live.join(","), known)
+        })
+        .collect();
+
+    let width = |header: &str, column: fn(&(String, String, String)) -> &String| {
+        rows.iter()
+            .map(|row| column(row).len())
+            .chain(Some(header.len()))
+            .max()
+            .unwrap()
+    };
+    let instruction_width = width("instruction", |row| &row.0);
+    let live_width = width("live", |row| &row.1);
+    let mut output = format!(
+        "{:>4}  {:<iw$}  {:<lw$}  known values\n",
+        "",
+        "instruction",
+        "live",
+        iw = instruction_width,
+        lw = live_width
+    );
+    for (i, (instruction, live, known)) in rows.iter().enumerate() {
+        output.push_str(&format!(
+            "{:>4}  {:<iw$}  {:<lw$}  {}\n",
+            i,
+            instruction,
+            live,
+            known,
+            iw = instruction_width,
+            lw = live_width
+        ));
+    }
+
+    output.push('\n');
+    match find_closed_form(&code, &start, &analysis.before) {
+        Some((start, form)) => output.push_str(&format!(
+            "The loop at {} computes {}, which is {}.\n",
+            start,
+            form,
+            form.evaluate()
+        )),
+        None => output.push_str("No closed form found.\n"),
+    }
+    Ok(output)
+}
@@ -0,0 +1,333 @@
+use crate::duet::bytecode::{Event, Machine, Program};
+use crate::duet::{Instruction, Number, Register, Value};
+
+/// The number of random register states a fused loop is checked against.
+const TRIALS: usize = 200;
+
+/// Running the original loop for longer than this during verification means
+/// the small values did not make it terminate, and the loop is left alone.
+const STEP_LIMIT: usize = 100_000;
+
+/// The loops are only computed directly while every value they involve is
+/// smaller than this, so that none of their instructions can overflow and
+/// the arithmetic the program runs with does not matter.
+const SAFE_LIMIT: Number = 1 << 31;
+
+/// A loop that can be computed directly instead of instruction by instruction.
+#[derive(Clone)]
+pub(super) enum Idiom {
+    /// `do { if x * y == z { flag = 0 } y += 1 } while y != z`, which leaves
+    /// `temp` at zero.
+    FactorScan {
+        x: Value,
+        y: Register,
+        z: Value,
+        flag: Register,
+        temp: Register,
+    },
+    /// `do { y = start; FactorScan; x += 1 } while x != z`, so `flag` is
+    /// cleared if `z` is a product of a number from `x..z` and one from
+    /// `start..z`.
+    FactorSearch {
+        x: Register,
+        y: Register,
+        start: Value,
+        z: Value,
+        flag: Register,
+        temp: Register,
+    },
+}
+
+/// A loop that is computed at once when the program reaches its first
+/// instruction. The instructions stay in place, so that jumps into the loop
+/// keep working, and they run one by one whenever the idiom does not apply.
+#[derive(Clone)]
+pub(super) struct Fused {
+    pub(super) idiom: Idiom,
+    /// The instruction right after the loop.
+    pub(super) end: usize,
+}
+
+/// A program along with the loops that start at each instruction.
+pub(super) struct Optimized {
+    pub(super) code: Vec<Instruction>,
+    pub(super) fused: Vec<Option<Fused>>,
+}
+
+fn resolve(machine: &Machine, value: &Value) -> Number {
+    match value {
+        Value::Register(r) => machine.get(*r),
+        Value::Literal(n) => *n,
+    }
+}
+
+fn safe(values: &[Number]) -> bool {
+    values.iter().all(|n| n.abs() < SAFE_LIMIT)
+}
+
+fn has_factor(x: Number, y_start: Number, z: Number) -> bool {
+    if x == 0 {
+        z == 0
+    } else {
+        z % x == 0 && z / x >= y_start && z / x < z
+    }
+}
+
+impl Fused {
+    /// Runs the whole loop and returns the instruction to continue at, or
+    /// `None` if the loop has to run instruction by instruction instead.
+    pub(super) fn execute(&self, machine: &mut Machine) -> Option<usize> {
+        match self.idiom {
+            Idiom::FactorScan {
+                ref x,
+                y,
+                ref z,
+                flag,
+                temp,
+            } => {
+                let (x, y_start, z) = (resolve(machine, x), machine.get(y), resolve(machine, z));
+                if y_start >= z || !safe(&[x, y_start, z]) {
+                    return None;
+                }
+                if has_factor(x, y_start, z) {
+                    machine.set(flag, 0);
+                }
+                machine.set(y, z);
+                machine.set(temp, 0);
+            }
+            Idiom::FactorSearch {
+                x,
+                y,
+                ref start,
+                ref z,
+                flag,
+                temp,
+            } => {
+                let (x_start, y_start, z) =
+                    (machine.get(x), resolve(machine, start), resolve(machine, z));
+                if x_start >= z || y_start >= z || !safe(&[x_start, y_start, z]) {
+                    return None;
+                }
+                if (x_start..z).any(|x| has_factor(x, y_start, z)) {
+                    machine.set(flag, 0);
+                }
+                machine.set(x, z);
+                machine.set(y, z);
+                machine.set(temp, 0);
+            }
+        }
+        Some(self.end)
+    }
+}
+
+fn uses(value: &Value, registers: &[Register]) -> bool {
+    match value {
+        Value::Register(r) => registers.contains(r),
+        Value::Literal(_) => false,
+    }
+}
+
+fn distinct(registers: &[Register]) -> bool {
+    registers
+        .iter()
+        .enumerate()
+        .all(|(i, r)| !registers[i + 1..].contains(r))
+}
+
+/// Matches
+///
+/// ```text
+/// set T X; mul T Y; sub T Z; jnz T 2; set F 0; sub Y -1; set T Y; sub T Z; jnz T -8
+/// ```
+fn match_factor_scan(code: &[Instruction], _: &[Option<Fused>], start: usize) -> Option<Fused> {
+    use Instruction::{Jnz, Mul, Set, Sub};
+    use Value::{Literal, Register as Reg};
+
+    match code.get(start..start + 9)? {
+        [Set(t0, x), Mul(t1, Reg(y0)), Sub(t2, z0), Jnz(Reg(t3), Literal(2)), Set(flag, Literal(0)), Sub(y1, Literal(-1)), Set(t4, Reg(y2)), Sub(t5, z1), Jnz(Reg(t6), Literal(-8))]
+            if [t1, t2, t3, t4, t5, t6].iter().all(|t| *t == t0)
+                && y0 == y1
+                && y0 == y2
+                && z0 == z1
+                && distinct(&[*t0, *y0, *flag])
+                && !uses(x, &[*t0, *y0, *flag])
+                && !uses(z0, &[*t0, *y0, *flag]) =>
+        {
+            Some(Fused {
+                idiom: Idiom::FactorScan {
+                    x: x.clone(),
+                    y: *y0,
+                    z: z0.clone(),
+                    flag: *flag,
+                    temp: *t0,
+                },
+                end: start + 9,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Matches
+///
+/// ```text
+/// set Y S; <FactorScan of X * Y>; sub X -1; set T X; sub T Z; jnz T -13
+/// ```
+fn match_factor_search(
+    code: &[Instruction],
+    fused: &[Option<Fused>],
+    start: usize,
+) -> Option<Fused> {
+    use Instruction::{Jnz, Set, Sub};
+    use Value::{Literal, Register as Reg};
+
+    let scan = match fused.get(start + 1)? {
+        Some(scan) if scan.end == start + 10 => scan,
+        _ => return None,
+    };
+    let (x, y, z, flag, temp) = match scan.idiom {
+        Idiom::FactorScan {
+            x: Reg(x),
+            y,
+            ref z,
+            flag,
+            temp,
+        } => (x, y, z, flag, temp),
+        _ => return None,
+    };
+
+    match (&code[start], code.get(start + 10..start + 14)?) {
+        (
+            Set(y0, y_start),
+            [Sub(x0, Literal(-1)), Set(t0, Reg(x1)), Sub(t1, z0), Jnz(Reg(t2), Literal(-13))],
+        ) if *y0 == y
+            && *x0 == x
+            && *x1 == x
+            && [t0, t1, t2].iter().all(|t| **t == temp)
+            && z0 == z
+            && distinct(&[x, y, flag, temp])
+            && !uses(y_start, &[x, y, flag, temp]) =>
+        {
+            Some(Fused {
+                idiom: Idiom::FactorSearch {
+                    x,
+                    y,
+                    start: y_start.clone(),
+                    z: z.clone(),
+                    flag,
+                    temp,
+                },
+                end: start + 14,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Recognizes an idiom starting at the given instruction, given the loops
+/// fused so far.
+type Matcher = fn(&[Instruction], &[Option<Fused>], usize) -> Option<Fused>;
+
+/// A xorshift generator, so that verification is the same on every run.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, low: Number, high: Number) -> Number {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        low + (self.0 % (high - low) as u64) as Number
+    }
+}
+
+/// Checks that the fused loop computes the same registers and continues at
+/// the same instruction as the original instructions, for random small
+/// register values.
+fn verify(program: &Program, start: usize, fused: &Fused) -> bool {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    let mut trials = 0;
+
+    for _ in 0..TRIALS * 10 {
+        if trials == TRIALS {
+            break;
+        }
+        let mut machine = Machine::new(program);
+        let mut expected = Machine::new(program);
+        for &r in program.registers() {
+            let value = random.next(-4, 16);
+            machine.set(r, value);
+            expected.set(r, value);
+        }
+
+        let end = match fused.execute(&mut expected) {
+            Some(end) => end,
+            None => continue,
+        };
+        trials += 1;
+
+        machine.jump(start);
+        let mut steps = 0;
+        while machine.pc() >= start && machine.pc() < fused.end && steps < STEP_LIMIT {
+            if machine.step().is_some() {
+                break;
+            }
+            steps += 1;
+        }
+
+        if machine.pc() != end
+            || program
+                .registers()
+                .iter()
+                .any(|&r| machine.get(r) != expected.get(r))
+        {
+            return false;
+        }
+    }
+
+    trials > 0
+}
+
+/// Finds the loops in `instructions` that match a known idiom, so that they
+/// can be computed directly. Inner loops are fused first so that the outer
+/// loops can be matched against them. Every candidate is checked against the
+/// original instructions before it is used.
+///
+/// The fused loops do not count their `mul` instructions, so this is only
+/// for running the program, not for counting.
+pub(super) fn optimize(instructions: &[Instruction]) -> Optimized {
+    let matchers: [Matcher; 2] = [match_factor_scan, match_factor_search];
+    let program = Program::compile(instructions);
+
+    let mut fused = vec![None; instructions.len()];
+    for matcher in matchers.iter() {
+        for start in 0..instructions.len() {
+            if let Some(candidate) = matcher(instructions, &fused, start) {
+                if verify(&program, start, &candidate) {
+                    fused[start] = Some(candidate);
+                }
+            }
+        }
+    }
+    Optimized {
+        code: instructions.to_vec(),
+        fused,
+    }
+}
+
+impl Optimized {
+    /// Runs a machine for the program until it stops, computing the fused
+    /// loops directly.
+    pub(super) fn run(&self, machine: &mut Machine) -> Event {
+        loop {
+            if let Some(Some(fused)) = self.fused.get(machine.pc()) {
+                if let Some(end) = fused.execute(machine) {
+                    machine.jump(end);
+                    continue;
+                }
+            }
+            if let Some(event) = machine.step() {
+                return event;
+            }
+        }
+    }
+}
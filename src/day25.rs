@@ -0,0 +1,456 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, digit1},
+    combinator::{map, map_res, opt, value},
+    multi::{many0, separated_list},
+    sequence::{delimited, pair, tuple},
+    IResult,
+};
+
+use crate::solver::{Answer, Parameter, Parameters};
+
+pub mod analysis;
+pub mod export;
+pub mod resume;
+mod tape;
+
+use tape::Tape;
+
+pub const PARAMETERS: &[Parameter] = &[
+    Parameter {
+        name: "steps",
+        default: "0",
+        description: "Number of steps to run, or 0 for the number in the blueprint",
+    },
+    Parameter {
+        name: "macro_steps",
+        default: "true",
+        description: "Whether to skip over blocks of the tape with cached macro-steps",
+    },
+];
+
+type State = String;
+
+/// A symbol that can be written on the tape, such as `0` or `1`.
+type Symbol = String;
+
+#[derive(Clone)]
+enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(Clone)]
+struct Instruction {
+    value_to_write: Symbol,
+    direction_to_move: Direction,
+    next_state: State,
+}
+
+impl Instruction {
+    fn new(input: (Symbol, Direction, State)) -> Instruction {
+        let (value_to_write, direction_to_move, next_state) = input;
+        Instruction {
+            value_to_write,
+            direction_to_move,
+            next_state,
+        }
+    }
+}
+
+/// The instructions of one state, for each symbol that it has a case for.
+/// Reading any other symbol halts the machine.
+#[derive(Clone)]
+struct Rule {
+    cases: Vec<(Symbol, Instruction)>,
+}
+
+struct Blueprint {
+    start: State,
+    /// The symbol that the tape is initially filled with.
+    blank: Symbol,
+    /// The state that halts the machine when it continues with it.
+    halt: Option<State>,
+    steps: u64,
+    rules: Vec<(State, Rule)>,
+}
+
+/// The state of a machine that has halted.
+const HALT: usize = usize::MAX;
+
+/// What the machine does in one state when it reads one symbol.
+#[derive(Clone, Copy)]
+struct Transition {
+    write: u8,
+    right: bool,
+    next: usize,
+}
+
+/// The effect of running from one cell of a block until the head leaves it.
+#[derive(Clone, Copy)]
+struct MacroStep {
+    cells: u8,
+    state: usize,
+    right: bool,
+    steps: u64,
+}
+
+#[derive(Clone, Copy)]
+enum Entry {
+    Unknown,
+    /// The head never leaves the block, because the machine loops or halts
+    /// in it.
+    Inside,
+    Known(MacroStep),
+}
+
+/// The macro-steps computed so far, for every state, block contents and
+/// position of the head in the block. A block is the byte of the tape that
+/// the head is in.
+struct MacroSteps {
+    entries: Vec<Entry>,
+    symbols: usize,
+    block: i64,
+}
+
+impl MacroSteps {
+    fn new(machine: &TuringMachine) -> MacroSteps {
+        let block = machine.tape.block_cells();
+        MacroSteps {
+            entries: vec![Entry::Unknown; machine.states.len() * 256 * block as usize],
+            symbols: machine.symbols.len(),
+            block,
+        }
+    }
+
+    fn get(
+        &mut self,
+        table: &[Option<Transition>],
+        state: usize,
+        cells: u8,
+        offset: i64,
+    ) -> Option<MacroStep> {
+        let index = (state * 256 + cells as usize) * self.block as usize + offset as usize;
+        if let Entry::Unknown = self.entries[index] {
+            self.entries[index] = self.compute(table, state, cells, offset);
+        }
+        match self.entries[index] {
+            Entry::Known(step) => Some(step),
+            _ => None,
+        }
+    }
+
+    fn compute(
+        &self,
+        table: &[Option<Transition>],
+        mut state: usize,
+        mut cells: u8,
+        mut offset: i64,
+    ) -> Entry {
+        let width = 8 / self.block as u32;
+        let mask = ((1u16 << width) - 1) as u8;
+        // Running longer than there are configurations means a loop.
+        let limit = self.entries.len() as u64;
+        let mut steps = 0;
+        while (0..self.block).contains(&offset) {
+            if steps > limit {
+                return Entry::Inside;
+            }
+            let bit = offset as u32 * width;
+            let read = (cells >> bit & mask) as usize;
+            let t = match table[state * self.symbols + read] {
+                Some(t) if t.next != HALT => t,
+                _ => return Entry::Inside,
+            };
+            cells = cells & !(mask << bit) | t.write << bit;
+            state = t.next;
+            offset += if t.right { 1 } else { -1 };
+            steps += 1;
+        }
+        Entry::Known(MacroStep {
+            cells,
+            state,
+            right: offset == self.block,
+            steps,
+        })
+    }
+}
+
+struct TuringMachine {
+    states: Vec<State>,
+    /// The alphabet, starting with the blank.
+    symbols: Vec<Symbol>,
+    /// The transition for every state and symbol read, at
+    /// `state * symbols.len() + symbol`. There is none where the machine
+    /// halts without a case for the symbol.
+    table: Vec<Option<Transition>>,
+    tape: Tape,
+    head: i64,
+    state: usize,
+}
+
+impl TuringMachine {
+    fn new(blueprint: &Blueprint) -> Result<TuringMachine, String> {
+        let states: Vec<State> = blueprint.rules.iter().map(|(s, _)| s.clone()).collect();
+        for (i, state) in states.iter().enumerate() {
+            if states[..i].contains(state) {
+                return Err(format!("state {} is defined twice", state));
+            }
+            if blueprint.halt.as_ref() == Some(state) {
+                return Err(format!("halt state {} has cases", state));
+            }
+        }
+        let index = |s: &State| {
+            if blueprint.halt.as_ref() == Some(s) {
+                Ok(HALT)
+            } else {
+                states
+                    .iter()
+                    .position(|r| r == s)
+                    .ok_or_else(|| format!("unknown state {}", s))
+            }
+        };
+
+        let mut symbols = vec![blueprint.blank.clone()];
+        for (_, rule) in blueprint.rules.iter() {
+            for (read, instruction) in rule.cases.iter() {
+                for symbol in [read, &instruction.value_to_write].iter() {
+                    if !symbols.contains(symbol) {
+                        symbols.push(symbol.to_string());
+                    }
+                }
+            }
+        }
+        if symbols.len() > 256 {
+            return Err(format!("{} symbols is more than 256", symbols.len()));
+        }
+        let symbol = |s: &Symbol| symbols.iter().position(|t| t == s).unwrap();
+
+        let mut table = vec![None; states.len() * symbols.len()];
+        for (state, (name, rule)) in blueprint.rules.iter().enumerate() {
+            for (read, instruction) in rule.cases.iter() {
+                let entry = &mut table[state * symbols.len() + symbol(read)];
+                if entry.is_some() {
+                    return Err(format!("state {} has two cases for {}", name, read));
+                }
+                *entry = Some(Transition {
+                    write: symbol(&instruction.value_to_write) as u8,
+                    right: matches!(instruction.direction_to_move, Direction::Right),
+                    next: index(&instruction.next_state)?,
+                });
+            }
+        }
+
+        Ok(TuringMachine {
+            state: index(&blueprint.start)?,
+            tape: Tape::new(symbols.len()),
+            states,
+            symbols,
+            table,
+            head: 0,
+        })
+    }
+
+    fn halted(&self) -> bool {
+        self.state == HALT
+    }
+
+    /// Runs one step, and returns whether there was one to run.
+    fn step(&mut self) -> bool {
+        if self.halted() {
+            return false;
+        }
+        let read = self.tape.get(self.head) as usize;
+        match self.table[self.state * self.symbols.len() + read] {
+            Some(t) => {
+                self.tape.set(self.head, t.write);
+                self.state = t.next;
+                self.head += if t.right { 1 } else { -1 };
+                true
+            }
+            None => {
+                self.state = HALT;
+                false
+            }
+        }
+    }
+
+    /// Runs at most `steps` steps, fewer if the machine halts, and returns
+    /// the number of steps run.
+    fn run(&mut self, steps: u64) -> u64 {
+        let mut run = 0;
+        while run < steps && self.step() {
+            run += 1;
+        }
+        run
+    }
+
+    /// Like `run`, but skips over whole blocks of the tape at once where it
+    /// can.
+    fn run_macro(&mut self, steps: u64, cache: &mut MacroSteps) -> u64 {
+        let block_cells = self.tape.block_cells();
+        let mut remaining = steps;
+        while remaining > 0 && !self.halted() {
+            let block = self.head.div_euclid(block_cells);
+            let cells = self.tape.byte(block);
+            let offset = self.head.rem_euclid(block_cells);
+            match cache.get(&self.table, self.state, cells, offset) {
+                Some(m) if m.steps <= remaining => {
+                    self.tape.set_byte(block, m.cells);
+                    self.state = m.state;
+                    self.head = if m.right {
+                        block_cells * (block + 1)
+                    } else {
+                        block_cells * block - 1
+                    };
+                    remaining -= m.steps;
+                }
+                _ => {
+                    if !self.step() {
+                        break;
+                    }
+                    remaining -= 1;
+                }
+            }
+        }
+        steps - remaining
+    }
+
+    /// The number of cells that are not blank.
+    fn diagnostic_checksum(&self) -> u64 {
+        self.tape.marked()
+    }
+}
+
+fn parse_val_inst(input: &str) -> IResult<&str, Symbol> {
+    delimited(tag("    - Write the value "), parse_symbol, tag(".\n"))(input)
+}
+
+fn parse_dir_inst(input: &str) -> IResult<&str, Direction> {
+    delimited(
+        tag("    - Move one slot to the "),
+        parse_direction,
+        tag(".\n"),
+    )(input)
+}
+
+fn parse_state_inst(input: &str) -> IResult<&str, State> {
+    delimited(tag("    - Continue with state "), parse_state, tag(".\n"))(input)
+}
+
+fn parse_state_specifier(input: &str) -> IResult<&str, State> {
+    delimited(tag("In state "), parse_state, tag(":\n"))(input)
+}
+
+fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
+    let parse_tuple = tuple((parse_val_inst, parse_dir_inst, parse_state_inst));
+    map(parse_tuple, Instruction::new)(input)
+}
+
+fn parse_case(input: &str) -> IResult<&str, (Symbol, Instruction)> {
+    let parse_value = delimited(tag("  If the current value is "), parse_symbol, tag(":\n"));
+    pair(parse_value, parse_instruction)(input)
+}
+
+fn parse_rule(input: &str) -> IResult<&str, (State, Rule)> {
+    let rule_parser = map(many0(parse_case), |cases| Rule { cases });
+    pair(parse_state_specifier, rule_parser)(input)
+}
+
+fn parse_rules(input: &str) -> IResult<&str, Vec<(State, Rule)>> {
+    separated_list(char('\n'), parse_rule)(input)
+}
+
+fn parse_direction(input: &str) -> IResult<&str, Direction> {
+    alt((
+        value(Direction::Left, tag("left")),
+        value(Direction::Right, tag("right")),
+    ))(input)
+}
+
+/// Names of states and symbols are made of letters, digits and underscores.
+fn parse_name(input: &str) -> IResult<&str, String> {
+    map(
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+        String::from,
+    )(input)
+}
+
+fn parse_state(input: &str) -> IResult<&str, State> {
+    parse_name(input)
+}
+
+fn parse_symbol(input: &str) -> IResult<&str, Symbol> {
+    parse_name(input)
+}
+
+fn parse_u64(input: &str) -> IResult<&str, u64> {
+    map_res(digit1, str::parse::<u64>)(input)
+}
+
+/// The start state, optional lines naming the blank symbol (`0` if there is
+/// none) and the halt state, and the number of steps.
+fn parse_blueprint(input: &str) -> IResult<&str, Blueprint> {
+    let start = delimited(tag("Begin in state "), parse_state, tag(".\n"));
+    let blank = opt(delimited(
+        tag("The blank symbol is "),
+        parse_symbol,
+        tag(".\n"),
+    ));
+    let halt = opt(delimited(tag("Halt in state "), parse_state, tag(".\n")));
+    let steps = delimited(
+        tag("Perform a diagnostic checksum after "),
+        parse_u64,
+        tag(" steps.\n\n"),
+    );
+    let blueprint = tuple((start, blank, halt, steps, parse_rules));
+    map(blueprint, |(start, blank, halt, steps, rules)| Blueprint {
+        start,
+        blank: blank.unwrap_or_else(|| "0".to_string()),
+        halt,
+        steps,
+        rules,
+    })(input)
+}
+
+fn parse_input(input: &str) -> Result<Blueprint, String> {
+    match parse_blueprint(input) {
+        Ok((rest, blueprint)) if rest.trim().is_empty() => Ok(blueprint),
+        Ok((rest, _)) => {
+            let rest = rest.trim_start();
+            Err(format!(
+                "line {}: cannot parse `{}`",
+                input[..input.len() - rest.len()].matches('\n').count() + 1,
+                rest.lines().next().unwrap_or("")
+            ))
+        }
+        Err(_) => Err(String::from("cannot parse the start state and step count")),
+    }
+}
+
+pub fn solve(input: &str, params: &Parameters) -> Vec<Answer> {
+    let blueprint = parse_input(input).unwrap();
+    let mut machine = TuringMachine::new(&blueprint).unwrap();
+
+    let num_steps = match params.get("steps") {
+        0 => blueprint.steps,
+        steps => steps,
+    };
+    let run = if params.get("macro_steps") {
+        let mut cache = MacroSteps::new(&machine);
+        machine.run_macro(num_steps, &mut cache)
+    } else {
+        machine.run(num_steps)
+    };
+
+    let description = if machine.halted() {
+        format!(
+            "The diagnostic checksum when the machine halts after {} steps",
+            run
+        )
+    } else {
+        format!("The diagnostic checksum after {} steps", num_steps)
+    };
+    vec![Answer::new(&description, machine.diagnostic_checksum())]
+}
@@ -0,0 +1,313 @@
+//! Classifies where a machine is heading: halting, returning to an earlier
+//! configuration, returning to one shifted along the tape, or running off
+//! into blank tape forever, with statistics on the run so far.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::{parse_input, Blueprint, Tape, TuringMachine, HALT};
+
+/// The number of cells at and behind a record position that are kept to
+/// detect translated cycles.
+const WINDOW: usize = 4096;
+
+/// The number of earlier records on each side that a new one is compared
+/// with.
+const RECORDS: usize = 64;
+
+enum Verdict {
+    /// The machine halted after the given number of steps.
+    Halted(u64),
+    /// The configuration after `start + period` steps is the one after
+    /// `start` steps.
+    Cycle { start: u64, period: u64 },
+    /// The configuration after `start + period` steps is the one after
+    /// `start` steps moved `shift` cells along the tape, with only blank
+    /// cells ahead of it.
+    TranslatedCycle { start: u64, period: u64, shift: i64 },
+    /// From `start` steps on, the head moves into blank tape in one direction
+    /// without ever turning back.
+    Runaway { start: u64, right: bool },
+    /// None of the above within the given number of steps.
+    Undecided(u64),
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = |right: bool| if right { "right" } else { "left" };
+        match self {
+            Verdict::Halted(steps) => write!(f, "halts after {} steps", steps),
+            Verdict::Cycle { start, period } => write!(
+                f,
+                "is in a cycle: from step {}, the configuration repeats every {} steps",
+                start, period
+            ),
+            Verdict::TranslatedCycle {
+                start,
+                period,
+                shift,
+            } => write!(
+                f,
+                "is in a translated cycle: from step {}, the configuration repeats every {} steps, shifted by {} to the {}",
+                start,
+                period,
+                shift.abs(),
+                side(*shift > 0)
+            ),
+            Verdict::Runaway { start, right } => write!(
+                f,
+                "runs away: from step {}, the head moves {} into blank tape forever",
+                start,
+                side(*right)
+            ),
+            Verdict::Undecided(steps) => write!(f, "is undecided after {} steps", steps),
+        }
+    }
+}
+
+/// A time when the head reached a cell that it never had on one side.
+struct Record {
+    steps: u64,
+    state: usize,
+    /// The position, in coordinates that grow towards the side.
+    x: i64,
+    /// The cells at and behind the position, nearest first.
+    behind: Vec<u8>,
+    /// The lowest coordinate of the head until the next record.
+    low: i64,
+}
+
+/// The records on one side of the tape.
+struct Side {
+    /// 1 for the right side and -1 for the left one.
+    direction: i64,
+    records: VecDeque<Record>,
+    farthest: i64,
+}
+
+impl Side {
+    fn new(direction: i64) -> Side {
+        Side {
+            direction,
+            records: VecDeque::new(),
+            farthest: 0,
+        }
+    }
+
+    /// Follows the head after a step, and returns a verdict if it reached a
+    /// new record that proves the machine runs forever.
+    fn observe(&mut self, machine: &TuringMachine, steps: u64) -> Option<Verdict> {
+        let x = self.direction * machine.head;
+        if let Some(last) = self.records.back_mut() {
+            last.low = last.low.min(x);
+        }
+        if x <= self.farthest {
+            return None;
+        }
+        self.farthest = x;
+
+        let right = self.direction > 0;
+        if runs_away(machine, right) {
+            return Some(Verdict::Runaway {
+                start: steps,
+                right,
+            });
+        }
+        let direction = self.direction;
+        let cell = |i: usize| machine.tape.get(direction * (x - i as i64));
+        // From an earlier record in the same state, the machine read no
+        // further back than `low`. If those cells are the same behind this
+        // record, and everything ahead is blank for both, it will do the
+        // same again from here, shifted.
+        let mut low = x;
+        for record in self.records.iter().rev() {
+            low = low.min(record.low);
+            let reach = (record.x - low) as usize;
+            if record.state == machine.state
+                && reach < WINDOW
+                && (0..=reach).all(|i| record.behind[i] == cell(i))
+            {
+                return Some(Verdict::TranslatedCycle {
+                    start: record.steps,
+                    period: steps - record.steps,
+                    shift: self.direction * (x - record.x),
+                });
+            }
+        }
+
+        if self.records.len() == RECORDS {
+            self.records.pop_front();
+        }
+        self.records.push_back(Record {
+            steps,
+            state: machine.state,
+            x,
+            behind: (0..WINDOW).map(cell).collect(),
+            low: x,
+        });
+        None
+    }
+}
+
+/// Whether the machine, with only blank cells ahead of the head, keeps
+/// moving towards them through a loop of states.
+fn runs_away(machine: &TuringMachine, right: bool) -> bool {
+    let mut seen = vec![false; machine.states.len()];
+    let mut state = machine.state;
+    while !seen[state] {
+        seen[state] = true;
+        match machine.table[state * machine.symbols.len()] {
+            Some(t) if t.next != HALT && t.right == right => state = t.next,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// A configuration of a machine.
+struct Snapshot {
+    state: usize,
+    head: i64,
+    tape: Tape,
+}
+
+impl Snapshot {
+    fn new(machine: &TuringMachine) -> Snapshot {
+        Snapshot {
+            state: machine.state,
+            head: machine.head,
+            tape: machine.tape.clone(),
+        }
+    }
+
+    fn matches(&self, machine: &TuringMachine) -> bool {
+        self.state == machine.state && self.head == machine.head && self.tape == machine.tape
+    }
+}
+
+/// The number of steps before a machine whose configurations repeat every
+/// `period` steps enters the cycle.
+fn cycle_start(blueprint: &Blueprint, period: u64) -> Result<u64, String> {
+    let mut first = TuringMachine::new(blueprint)?;
+    let mut second = TuringMachine::new(blueprint)?;
+    second.run(period);
+    let mut start = 0;
+    while (first.state, first.head) != (second.state, second.head) || first.tape != second.tape {
+        first.step();
+        second.step();
+        start += 1;
+    }
+    Ok(start)
+}
+
+struct Analysis {
+    verdict: Verdict,
+    steps: u64,
+    /// The leftmost and rightmost positions of the head.
+    extent: (i64, i64),
+    /// The names of the states, and the number of steps run in each one.
+    visits: Vec<(String, u64)>,
+    marked: u64,
+}
+
+/// Runs a machine for at most `limit` steps, or until it can tell how the run
+/// ends.
+fn analyze(blueprint: &Blueprint, limit: u64) -> Result<Analysis, String> {
+    let mut machine = TuringMachine::new(blueprint)?;
+    let mut visits = vec![0; machine.states.len()];
+    let mut extent = (0, 0);
+    let mut sides = [Side::new(1), Side::new(-1)];
+    // Brent's cycle detection: compare with the configuration saved at the
+    // last power of two.
+    let mut saved = Snapshot::new(&machine);
+    let mut power = 1;
+    let mut since_saved = 0;
+
+    let mut steps = 0;
+    let verdict = loop {
+        if machine.halted() {
+            break Verdict::Halted(steps);
+        }
+        if steps == limit {
+            break Verdict::Undecided(steps);
+        }
+        let state = machine.state;
+        if !machine.step() {
+            break Verdict::Halted(steps);
+        }
+        steps += 1;
+        visits[state] += 1;
+        extent = (extent.0.min(machine.head), extent.1.max(machine.head));
+        if machine.halted() {
+            continue;
+        }
+        if let Some(verdict) = sides.iter_mut().find_map(|s| s.observe(&machine, steps)) {
+            break verdict;
+        }
+
+        since_saved += 1;
+        if saved.matches(&machine) {
+            break Verdict::Cycle {
+                start: cycle_start(blueprint, since_saved)?,
+                period: since_saved,
+            };
+        }
+        if since_saved == power {
+            saved = Snapshot::new(&machine);
+            power *= 2;
+            since_saved = 0;
+        }
+    };
+
+    Ok(Analysis {
+        verdict,
+        steps,
+        extent,
+        visits: machine.states.iter().cloned().zip(visits).collect(),
+        marked: machine.diagnostic_checksum(),
+    })
+}
+
+/// Analyzes the blueprint in `input` for at most `limit` steps, or the number
+/// in the blueprint if there is no limit.
+pub fn report(input: &str, limit: Option<u64>) -> Result<String, String> {
+    let blueprint = parse_input(input)?;
+    let analysis = analyze(&blueprint, limit.unwrap_or(blueprint.steps))?;
+
+    let (left, right) = analysis.extent;
+    let mut output = format!("The machine {}.\n\n", analysis.verdict);
+    output.push_str(&format!("Steps run:    {}\n", analysis.steps));
+    output.push_str(&format!(
+        "Tape extent:  {}..={} ({} cells)\n",
+        left,
+        right,
+        right - left + 1
+    ));
+    output.push_str(&format!("Marked cells: {}\n\n", analysis.marked));
+
+    let width = analysis
+        .visits
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max("state".len());
+    output.push_str(&format!(
+        "{:<w$}  {:>12}  {:>6}\n",
+        "state",
+        "visits",
+        "share",
+        w = width
+    ));
+    for (name, visits) in analysis.visits.iter() {
+        let share = 100.0 * *visits as f64 / analysis.steps.max(1) as f64;
+        output.push_str(&format!(
+            "{:<w$}  {:>12}  {:>5.1}%\n",
+            name,
+            visits,
+            share,
+            w = width
+        ));
+    }
+    Ok(output)
+}
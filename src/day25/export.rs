@@ -0,0 +1,158 @@
+//! Blueprints as a Graphviz DOT state diagram or a Markdown transition table,
+//! and the tape of a running machine as text strips.
+
+use super::{parse_input, Blueprint, Direction, Instruction, MacroSteps, TuringMachine};
+
+/// The name of the halt state that cases missing from a state lead to, which
+/// cannot clash with the name of a state in the blueprint.
+const IMPLICIT_HALT: &str = "(halt)";
+
+fn direction(instruction: &Instruction) -> &'static str {
+    match instruction.direction_to_move {
+        Direction::Left => "L",
+        Direction::Right => "R",
+    }
+}
+
+/// The alphabet in the order the machine numbers it, starting with the blank.
+fn symbols(blueprint: &Blueprint) -> Result<Vec<String>, String> {
+    TuringMachine::new(blueprint).map(|machine| machine.symbols)
+}
+
+/// The state diagram, with an edge labelled `read/write/move` for every case.
+/// Symbols that a state has no case for lead to an extra halt node.
+pub fn dot(input: &str) -> Result<String, String> {
+    let blueprint = parse_input(input)?;
+    let symbols = symbols(&blueprint)?;
+
+    let mut dot =
+        String::from("digraph blueprint {\n    node [shape=circle, fontname=monospace];\n");
+    dot.push_str("    start [shape=point];\n");
+    dot.push_str(&format!("    start -> \"{}\";\n", blueprint.start));
+    if let Some(halt) = &blueprint.halt {
+        dot.push_str(&format!("    \"{}\" [shape=doublecircle];\n", halt));
+    }
+
+    let mut implicit_halt = false;
+    for (state, rule) in blueprint.rules.iter() {
+        for symbol in symbols.iter() {
+            match rule.cases.iter().find(|(read, _)| read == symbol) {
+                Some((read, instruction)) => dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}/{}/{}\"];\n",
+                    state,
+                    instruction.next_state,
+                    read,
+                    instruction.value_to_write,
+                    direction(instruction)
+                )),
+                None => {
+                    implicit_halt = true;
+                    dot.push_str(&format!(
+                        "    \"{}\" -> \"{}\" [style=dashed, label=\"{}\"];\n",
+                        state, IMPLICIT_HALT, symbol
+                    ));
+                }
+            }
+        }
+    }
+    if implicit_halt {
+        dot.push_str(&format!(
+            "    \"{}\" [shape=doublecircle, style=dashed];\n",
+            IMPLICIT_HALT
+        ));
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// The transition table, with a row for every state and a column for every
+/// symbol read.
+pub fn markdown(input: &str) -> Result<String, String> {
+    let blueprint = parse_input(input)?;
+    let symbols = symbols(&blueprint)?;
+
+    let mut md = format!(
+        "Start in state `{}` on a tape of `{}`, and stop after {} steps",
+        blueprint.start, blueprint.blank, blueprint.steps
+    );
+    match &blueprint.halt {
+        Some(halt) => md.push_str(&format!(" or in state `{}`.\n\n", halt)),
+        None => md.push_str(".\n\n"),
+    }
+    md.push_str("| State |");
+    for symbol in symbols.iter() {
+        md.push_str(&format!(" Read `{}` |", symbol));
+    }
+    md.push_str("\n|-------|");
+    for _ in symbols.iter() {
+        md.push_str("----------|");
+    }
+    md.push('\n');
+    for (state, rule) in blueprint.rules.iter() {
+        md.push_str(&format!("| `{}` |", state));
+        for symbol in symbols.iter() {
+            match rule.cases.iter().find(|(read, _)| read == symbol) {
+                Some((_, instruction)) => md.push_str(&format!(
+                    " `{}` {} `{}` |",
+                    instruction.value_to_write,
+                    direction(instruction),
+                    instruction.next_state
+                )),
+                None => md.push_str(" halt |"),
+            }
+        }
+        md.push('\n');
+    }
+    Ok(md)
+}
+
+/// The cells within `radius` of the head, with the one under the head in
+/// brackets.
+fn strip(machine: &TuringMachine, radius: i64) -> String {
+    let width = machine.symbols.iter().map(|s| s.len()).max().unwrap_or(1);
+    (machine.head - radius..=machine.head + radius)
+        .map(|position| {
+            let symbol = &machine.symbols[machine.tape.get(position) as usize];
+            if position == machine.head {
+                format!("[{:^w$}]", symbol, w = width)
+            } else {
+                format!(" {:^w$} ", symbol, w = width)
+            }
+        })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// The tape around the head after each of the given numbers of steps, with
+/// the state the machine is in then, if it has not halted.
+pub fn snapshots(input: &str, steps: &[u64], radius: i64) -> Result<String, String> {
+    let blueprint = parse_input(input)?;
+    let mut machine = TuringMachine::new(&blueprint)?;
+    let mut cache = MacroSteps::new(&machine);
+    let mut steps = steps.to_vec();
+    steps.sort_unstable();
+    steps.dedup();
+
+    let mut output = String::new();
+    let mut run = 0;
+    for &step in steps.iter() {
+        if !machine.halted() {
+            run += machine.run_macro(step - run, &mut cache);
+        }
+        if machine.halted() {
+            output.push_str(&format!("Step {}: halted after {} steps", step, run));
+        } else {
+            output.push_str(&format!(
+                "Step {}, state {}",
+                step, machine.states[machine.state]
+            ));
+        }
+        output.push_str(&format!(
+            ", head at {}:\n{}\n",
+            machine.head,
+            strip(&machine, radius)
+        ));
+    }
+    Ok(output)
+}
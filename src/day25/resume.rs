@@ -0,0 +1,196 @@
+//! Long runs of a machine that save checkpoints as they go, and can resume
+//! from one of them after being stopped.
+//!
+//! After its header fields, a checkpoint has a line for every word of the
+//! tape from the first one with a symbol that is not blank to the last one,
+//! in hexadecimal, with the first cell in the lowest bits.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::tape::Tape;
+use super::{parse_input, MacroSteps, TuringMachine, HALT};
+use crate::cache;
+use crate::checkpoint::{self, Checkpoint, Schedule};
+
+const KIND: &str = "day25-checkpoint";
+const VERSION: u32 = 1;
+
+/// What the `state` field holds once the machine has halted, which cannot
+/// clash with the name of a state.
+const HALTED: &str = "(halt)";
+
+fn to_checkpoint(machine: &TuringMachine, steps: u64, input: &str) -> Checkpoint {
+    let (start, words) = machine.tape.words();
+    let mut checkpoint = Checkpoint::new();
+    checkpoint.set("input", cache::hash_input(input));
+    checkpoint.set("steps", steps);
+    match machine.states.get(machine.state) {
+        Some(state) => checkpoint.set("state", state),
+        None => checkpoint.set("state", HALTED),
+    }
+    checkpoint.set("head", machine.head);
+    checkpoint.set("start", start);
+    checkpoint.lines = words.iter().map(|word| format!("{:016x}", word)).collect();
+    checkpoint
+}
+
+fn save(machine: &TuringMachine, steps: u64, input: &str, path: &Path) -> Result<(), String> {
+    to_checkpoint(machine, steps, input)
+        .save(path, KIND, VERSION)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Puts `machine` in the configuration saved in the checkpoint at `path`,
+/// which must have been made from the same input, and returns the number of
+/// steps run until then.
+fn load(machine: &mut TuringMachine, input: &str, path: &Path) -> Result<u64, String> {
+    let error = |e: String| format!("{}: {}", path.display(), e);
+    let get = |e: io::Error| error(e.to_string());
+    let checkpoint = Checkpoint::load(path, KIND, VERSION).map_err(get)?;
+    if checkpoint.get::<String>("input").map_err(get)? != cache::hash_input(input) {
+        return Err(error(String::from("made from a different input")));
+    }
+
+    let state: String = checkpoint.get("state").map_err(get)?;
+    machine.state = if state == HALTED {
+        HALT
+    } else {
+        machine
+            .states
+            .iter()
+            .position(|s| *s == state)
+            .ok_or_else(|| error(format!("unknown state {}", state)))?
+    };
+    machine.head = checkpoint.get("head").map_err(get)?;
+
+    let words = checkpoint
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            u64::from_str_radix(line, 16)
+                .map_err(|_| error(format!("invalid word on line {}", i + 2)))
+        })
+        .collect::<Result<Vec<u64>, String>>()?;
+    let start = checkpoint.get("start").map_err(get)?;
+    machine.tape = Tape::from_words(machine.symbols.len(), start, &words)
+        .ok_or_else(|| error(String::from("invalid tape")))?;
+    checkpoint.get("steps").map_err(get)
+}
+
+/// What the solver reports after the run.
+fn describe(machine: &TuringMachine, steps: u64) -> String {
+    if machine.halted() {
+        format!(
+            "The diagnostic checksum when the machine halts after {} steps: {}\n",
+            steps,
+            machine.diagnostic_checksum()
+        )
+    } else {
+        format!(
+            "The diagnostic checksum after {} steps: {}\n",
+            steps,
+            machine.diagnostic_checksum()
+        )
+    }
+}
+
+/// Runs the machine in `input` until it halts or has run `limit` steps, or
+/// the number in the blueprint if there is no limit, starting from a
+/// checkpoint if the schedule names one and saving checkpoints on it.
+pub fn run(input: &str, limit: Option<u64>, schedule: &Schedule) -> Result<String, String> {
+    let blueprint = parse_input(input)?;
+    let limit = limit.unwrap_or(blueprint.steps);
+    let mut machine = TuringMachine::new(&blueprint)?;
+    let mut cache = MacroSteps::new(&machine);
+
+    let mut output = String::new();
+    let mut steps = 0;
+    if let Some(path) = &schedule.resume {
+        steps = load(&mut machine, input, path)?;
+        output.push_str(&format!(
+            "Resumed from {} after {} steps\n",
+            path.display(),
+            steps
+        ));
+    }
+    if steps > limit {
+        return Err(format!("the checkpoint is already past {} steps", limit));
+    }
+
+    let mut saved = None;
+    while steps < limit && !machine.halted() {
+        let stop = schedule.next_stop(steps, limit);
+        steps += machine.run_macro(stop - steps, &mut cache);
+        if steps == stop && schedule.is_due(stop) {
+            save(&machine, steps, input, &schedule.path)?;
+            saved = Some(stop);
+        }
+    }
+    if let Some(stop) = saved {
+        output.push_str(&format!(
+            "Saved the last checkpoint to {} after {} steps\n",
+            schedule.path.display(),
+            stop
+        ));
+    }
+    output.push_str(&describe(&machine, steps));
+    Ok(output)
+}
+
+/// Runs the machine in `input` for `limit` steps straight, and again stopping
+/// after a third and two thirds of them to save a checkpoint and resume from
+/// it, and checks that both runs end in the same configuration.
+pub fn check(input: &str, limit: Option<u64>) -> Result<String, String> {
+    let blueprint = parse_input(input)?;
+    let limit = limit.unwrap_or(blueprint.steps);
+
+    let mut straight = TuringMachine::new(&blueprint)?;
+    let mut cache = MacroSteps::new(&straight);
+    let straight_steps = straight.run_macro(limit, &mut cache);
+
+    let path = checkpoint::temp_path(KIND);
+    let mut resumed = TuringMachine::new(&blueprint)?;
+    let mut steps = 0;
+    for &stop in [limit / 3, limit / 3 * 2].iter() {
+        if stop > steps {
+            steps += resumed.run_macro(stop - steps, &mut cache);
+        }
+        save(&resumed, steps, input, &path)?;
+        resumed = TuringMachine::new(&blueprint)?;
+        steps = load(&mut resumed, input, &path)?;
+    }
+    fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    steps += resumed.run_macro(limit - steps, &mut cache);
+
+    let expected = to_checkpoint(&straight, straight_steps, input).contents(KIND, VERSION);
+    let actual = to_checkpoint(&resumed, steps, input).contents(KIND, VERSION);
+    if actual != expected {
+        return Err(format!(
+            "resuming from checkpoints after {} and {} steps changes the configuration after {}",
+            limit / 3,
+            limit / 3 * 2,
+            limit
+        ));
+    }
+    Ok(format!(
+        "Resuming from checkpoints after {} and {} steps gives the same configuration as a straight run. {}",
+        limit / 3,
+        limit / 3 * 2,
+        describe(&resumed, steps)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resuming_matches_a_straight_run() {
+        let input = fs::read_to_string("data/day25.txt").unwrap();
+        check(&input, Some(1000000)).unwrap();
+        check(&input, None).unwrap();
+    }
+}
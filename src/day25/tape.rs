@@ -0,0 +1,183 @@
+//! A tape of symbols that grows in both directions, with cells of 1, 2, 4 or
+//! 8 bits packed in every word. Symbol 0 is the blank.
+
+#[derive(Clone)]
+pub struct Tape {
+    words: Vec<u64>,
+    /// The number of bits in every cell.
+    width: u32,
+    /// The position of the first cell of `words[0]`, a multiple of the number
+    /// of cells in a word.
+    start: i64,
+    /// The number of cells that are not blank, kept up to date by every write.
+    marked: u64,
+}
+
+impl Tape {
+    /// A blank tape with cells wide enough for `symbols` symbols, at most 256.
+    pub fn new(symbols: usize) -> Tape {
+        let width = match symbols {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        };
+        Tape {
+            words: vec![0; 2],
+            width,
+            start: -(64 / width as i64),
+            marked: 0,
+        }
+    }
+
+    /// A tape with the given words from `start`, as `words` returns them, or
+    /// `None` if `start` is not where a word starts or a cell holds a symbol
+    /// beyond the alphabet.
+    pub fn from_words(symbols: usize, start: i64, words: &[u64]) -> Option<Tape> {
+        let mut tape = Tape::new(symbols);
+        if words.is_empty() {
+            return Some(tape);
+        }
+        if start % tape.word_cells() != 0 {
+            return None;
+        }
+        tape.words = words.to_vec();
+        tape.start = start;
+        for position in start..tape.end() {
+            match tape.get(position) as usize {
+                0 => {}
+                symbol if symbol < symbols => tape.marked += 1,
+                _ => return None,
+            }
+        }
+        Some(tape)
+    }
+
+    /// The position of the first cell of the first word with a symbol that
+    /// is not blank, and the words from there to the last one with such a
+    /// symbol.
+    pub fn words(&self) -> (i64, &[u64]) {
+        match self.words.iter().position(|&word| word != 0) {
+            Some(first) => {
+                let last = self.words.iter().rposition(|&word| word != 0).unwrap();
+                let start = self.start + self.word_cells() * first as i64;
+                (start, &self.words[first..=last])
+            }
+            None => (0, &[]),
+        }
+    }
+
+    /// The number of cells in a block, that is in one byte.
+    pub fn block_cells(&self) -> i64 {
+        8 / self.width as i64
+    }
+
+    fn word_cells(&self) -> i64 {
+        64 / self.width as i64
+    }
+
+    fn mask(&self) -> u64 {
+        (1 << self.width) - 1
+    }
+
+    fn end(&self) -> i64 {
+        self.start + self.word_cells() * self.words.len() as i64
+    }
+
+    /// Grows the tape until it covers `position`, doubling it on the side
+    /// where it is missing.
+    fn reserve(&mut self, position: i64) {
+        while position < self.start {
+            let added = self.words.len();
+            let mut words = vec![0; added];
+            words.extend_from_slice(&self.words);
+            self.words = words;
+            self.start -= self.word_cells() * added as i64;
+        }
+        while position >= self.end() {
+            let len = self.words.len();
+            self.words.resize(2 * len, 0);
+        }
+    }
+
+    /// The word and bit of a position on the tape.
+    fn locate(&self, position: i64) -> (usize, u32) {
+        let index = (position - self.start) as usize;
+        let cells = self.word_cells() as usize;
+        (index / cells, (index % cells) as u32 * self.width)
+    }
+
+    pub fn get(&self, position: i64) -> u8 {
+        if position < self.start || position >= self.end() {
+            return 0;
+        }
+        let (word, bit) = self.locate(position);
+        (self.words[word] >> bit & self.mask()) as u8
+    }
+
+    pub fn set(&mut self, position: i64, symbol: u8) {
+        self.reserve(position);
+        let (word, bit) = self.locate(position);
+        let old = self.words[word] >> bit & self.mask();
+        self.marked = self.marked + (symbol != 0) as u64 - (old != 0) as u64;
+        self.words[word] = self.words[word] & !(self.mask() << bit) | (symbol as u64) << bit;
+    }
+
+    /// The cells of a block, the first one in the lowest bits.
+    pub fn byte(&self, block: i64) -> u8 {
+        let position = self.block_cells() * block;
+        if position < self.start || position >= self.end() {
+            return 0;
+        }
+        let (word, bit) = self.locate(position);
+        (self.words[word] >> bit) as u8
+    }
+
+    pub fn set_byte(&mut self, block: i64, cells: u8) {
+        let position = self.block_cells() * block;
+        self.reserve(position);
+        let (word, bit) = self.locate(position);
+        let old = (self.words[word] >> bit) as u8;
+        self.marked = self.marked + self.count_marked(cells) - self.count_marked(old);
+        self.words[word] = self.words[word] & !(0xff << bit) | (cells as u64) << bit;
+    }
+
+    fn count_marked(&self, cells: u8) -> u64 {
+        if self.width == 1 {
+            return cells.count_ones() as u64;
+        }
+        (0..8)
+            .step_by(self.width as usize)
+            .filter(|&bit| cells as u64 >> bit & self.mask() != 0)
+            .count() as u64
+    }
+
+    /// The number of cells that are not blank.
+    pub fn marked(&self) -> u64 {
+        self.marked
+    }
+
+    /// The word of cells starting at `position`, a multiple of the number
+    /// of cells in a word.
+    fn word(&self, position: i64) -> u64 {
+        if position < self.start || position >= self.end() {
+            return 0;
+        }
+        self.words[((position - self.start) / self.word_cells()) as usize]
+    }
+}
+
+impl PartialEq for Tape {
+    /// Tapes are equal when all their cells are, however far each one has
+    /// grown.
+    fn eq(&self, other: &Tape) -> bool {
+        if self.width != other.width || self.marked != other.marked {
+            return false;
+        }
+        let start = self.start.min(other.start);
+        let end = self.end().max(other.end());
+        (start..end)
+            .step_by(self.word_cells() as usize)
+            .all(|p| self.word(p) == other.word(p))
+    }
+}
@@ -0,0 +1,267 @@
+//! The assembly language shared by day 18 (Duet) and day 23 (the coprocessor),
+//! for tools that work on programs of either day.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while},
+    character::complete::{digit1, one_of},
+    combinator::{all_consuming, map, map_opt, map_res, opt, recognize},
+    sequence::{pair, preceded, separated_pair, tuple},
+    IResult,
+};
+
+pub mod assemble;
+pub mod bytecode;
+pub mod decompile;
+pub mod network;
+pub mod trace;
+pub mod transpile;
+
+pub type Number = i64;
+
+/// The longest register name.
+pub const MAX_NAME_LENGTH: usize = 15;
+
+/// The name of a register: a lowercase letter, followed by lowercase letters,
+/// digits and underscores. The name is stored inline so that registers can be
+/// copied around like the single letters of the puzzles.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Register {
+    bytes: [u8; MAX_NAME_LENGTH],
+    len: u8,
+}
+
+impl Register {
+    /// The register with the given name, if it is a valid one.
+    pub fn new(name: &str) -> Option<Register> {
+        let valid = name.len() <= MAX_NAME_LENGTH
+            && name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid {
+            return None;
+        }
+        let mut bytes = [0; MAX_NAME_LENGTH];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        Some(Register {
+            bytes,
+            len: name.len() as u8,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap()
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    Register(Register),
+    Literal(Number),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Register(r) => write!(f, "{}", r),
+            Value::Literal(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// The instructions of both days. Day 18 uses `snd`, `add`, `rcv` and `jgz`,
+/// day 23 uses `sub` and `jnz`, and both use `set`, `mul` and `mod`.
+#[derive(Clone, PartialEq)]
+pub enum Instruction {
+    Snd(Value),
+    Set(Register, Value),
+    Add(Register, Value),
+    Sub(Register, Value),
+    Mul(Register, Value),
+    Mod(Register, Value),
+    Rcv(Register),
+    Jgz(Value, Value),
+    Jnz(Value, Value),
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> &'static str {
+        match self {
+            Instruction::Snd(_) => "snd",
+            Instruction::Set(..) => "set",
+            Instruction::Add(..) => "add",
+            Instruction::Sub(..) => "sub",
+            Instruction::Mul(..) => "mul",
+            Instruction::Mod(..) => "mod",
+            Instruction::Rcv(_) => "rcv",
+            Instruction::Jgz(..) => "jgz",
+            Instruction::Jnz(..) => "jnz",
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Snd(v) => write!(f, "snd {}", v),
+            Instruction::Set(r, v) => write!(f, "set {} {}", r, v),
+            Instruction::Add(r, v) => write!(f, "add {} {}", r, v),
+            Instruction::Sub(r, v) => write!(f, "sub {} {}", r, v),
+            Instruction::Mul(r, v) => write!(f, "mul {} {}", r, v),
+            Instruction::Mod(r, v) => write!(f, "mod {} {}", r, v),
+            Instruction::Rcv(r) => write!(f, "rcv {}", r),
+            Instruction::Jgz(v1, v2) => write!(f, "jgz {} {}", v1, v2),
+            Instruction::Jnz(v1, v2) => write!(f, "jnz {} {}", v1, v2),
+        }
+    }
+}
+
+/// When a jump is taken, as far as can be told without running the program.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Condition {
+    Always,
+    Never,
+    /// `jgz` with a register.
+    Positive(Register),
+    /// `jnz` with a register.
+    NonZero(Register),
+}
+
+impl Instruction {
+    /// The condition and offset of a jump, or `None` for other instructions.
+    pub fn jump(&self) -> Option<(Condition, &Value)> {
+        match self {
+            Instruction::Jgz(Value::Literal(n), offset) => Some((
+                if *n > 0 {
+                    Condition::Always
+                } else {
+                    Condition::Never
+                },
+                offset,
+            )),
+            Instruction::Jgz(Value::Register(r), offset) => Some((Condition::Positive(*r), offset)),
+            Instruction::Jnz(Value::Literal(n), offset) => Some((
+                if *n != 0 {
+                    Condition::Always
+                } else {
+                    Condition::Never
+                },
+                offset,
+            )),
+            Instruction::Jnz(Value::Register(r), offset) => Some((Condition::NonZero(*r), offset)),
+            _ => None,
+        }
+    }
+
+    /// The instruction a jump at `index` goes to, if its offset is a literal.
+    /// The target can be outside of the program, which ends it, and so does
+    /// a literal offset too large to add, for which this is `None` as well.
+    pub fn jump_target(&self, index: usize) -> Option<Number> {
+        match self.jump() {
+            Some((_, Value::Literal(offset))) => (index as Number).checked_add(*offset),
+            _ => None,
+        }
+    }
+}
+
+/// The first instruction of every basic block.
+pub fn leaders(program: &[Instruction]) -> BTreeSet<usize> {
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+    for (i, instruction) in program.iter().enumerate() {
+        if instruction.jump().is_some() {
+            leaders.insert(i + 1);
+            if let Some(t) = instruction.jump_target(i) {
+                if t >= 0 && (t as usize) < program.len() {
+                    leaders.insert(t as usize);
+                }
+            }
+        }
+    }
+    leaders.retain(|&l| l < program.len());
+    leaders
+}
+
+fn parse_literal(input: &str) -> IResult<&str, Number> {
+    let r = recognize(pair(opt(tag("-")), digit1));
+    map_res(r, str::parse::<Number>)(input)
+}
+
+fn parse_register(input: &str) -> IResult<&str, Register> {
+    let name = recognize(pair(
+        one_of("abcdefghijklmnopqrstuvwxyz"),
+        take_while(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+    ));
+    map_opt(name, Register::new)(input)
+}
+
+fn parse_value(input: &str) -> IResult<&str, Value> {
+    let parse_register_as_value = map(parse_register, Value::Register);
+    let parse_literal_as_value = map(parse_literal, Value::Literal);
+    alt((parse_register_as_value, parse_literal_as_value))(input)
+}
+
+fn parse_instruction_val_val(input: &str) -> IResult<&str, Instruction> {
+    let (rest, (opcode, (v1, v2))) = pair(
+        alt((tag("jgz "), tag("jnz "))),
+        separated_pair(parse_value, tag(" "), parse_value),
+    )(input)?;
+    let instruction = match opcode {
+        "jgz " => Instruction::Jgz(v1, v2),
+        "jnz " => Instruction::Jnz(v1, v2),
+        _ => unreachable!(),
+    };
+    Ok((rest, instruction))
+}
+
+fn parse_instruction_reg_val(input: &str) -> IResult<&str, Instruction> {
+    let parse_opcode = alt((tag("set"), tag("add"), tag("sub"), tag("mul"), tag("mod")));
+    let (rest, (opcode, r, v)) = tuple((
+        parse_opcode,
+        preceded(tag(" "), parse_register),
+        preceded(tag(" "), parse_value),
+    ))(input)?;
+    let instruction = match opcode {
+        "set" => Instruction::Set(r, v),
+        "add" => Instruction::Add(r, v),
+        "sub" => Instruction::Sub(r, v),
+        "mul" => Instruction::Mul(r, v),
+        "mod" => Instruction::Mod(r, v),
+        _ => unreachable!(),
+    };
+    Ok((rest, instruction))
+}
+
+fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
+    alt((
+        parse_instruction_val_val,
+        parse_instruction_reg_val,
+        map(preceded(tag("snd "), parse_value), Instruction::Snd),
+        map(preceded(tag("rcv "), parse_register), Instruction::Rcv),
+    ))(input)
+}
+
+/// Parses a program of either day, one instruction per line.
+pub fn parse(input: &str) -> Result<Vec<Instruction>, String> {
+    input
+        .trim_end()
+        .lines()
+        .enumerate()
+        .map(
+            |(i, line)| match all_consuming(parse_instruction)(line.trim_end()) {
+                Ok((_, instruction)) => Ok(instruction),
+                Err(_) => Err(format!("line {}: cannot parse `{}`", i + 1, line)),
+            },
+        )
+        .collect()
+}
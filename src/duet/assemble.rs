@@ -0,0 +1,555 @@
+//! An assembler for a small structured language, so that programs for either
+//! day can be written without counting jump offsets by hand.
+//!
+//! ```text
+//! # Comments start with `#` or `;`.
+//! const LIMIT = 10
+//!
+//!     set i LIMIT
+//!     while i > 0 {         # `x > 0` and `x <= 0` for day 18,
+//!         snd i             # `x != 0` and `x == 0` for day 23,
+//!         add i -1          # and `true` or `false` for both
+//!     }
+//!     if j > 0 {
+//!         goto done
+//!     } else {
+//!         halt
+//!     }
+//! done:
+//!     jgz 1 done            # jumps take a label or an offset
+//! ```
+//!
+//! Loops also support `break` and `continue`, and `halt` jumps out of the
+//! program. Registers can have any name the parser accepts, such as `count`;
+//! a name cannot be both a register and a constant or a label. The target of
+//! a jump instruction is a label if there is one by that name, and otherwise
+//! a register that holds the offset.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Instruction, Number, Register, Value};
+
+/// The instruction set to assemble for.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dialect {
+    Day18,
+    Day23,
+}
+
+impl Dialect {
+    pub fn parse(s: &str) -> Result<Dialect, String> {
+        match s {
+            "day18" => Ok(Dialect::Day18),
+            "day23" => Ok(Dialect::Day23),
+            _ => Err(format!("unknown dialect: {}", s)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Dialect::Day18 => "day18",
+            Dialect::Day23 => "day23",
+        }
+    }
+
+    fn opcodes(self) -> &'static [&'static str] {
+        match self {
+            Dialect::Day18 => &["snd", "set", "add", "mul", "mod", "rcv", "jgz"],
+            Dialect::Day23 => &["set", "sub", "mul", "mod", "jnz"],
+        }
+    }
+
+    /// The conditional jump of the dialect.
+    fn jump(self, condition: Value, offset: Value) -> Instruction {
+        match self {
+            Dialect::Day18 => Instruction::Jgz(condition, offset),
+            Dialect::Day23 => Instruction::Jnz(condition, offset),
+        }
+    }
+
+    /// Parses a program, and checks that it only uses the instructions of
+    /// the dialect.
+    pub fn parse_program(self, text: &str) -> Result<Vec<Instruction>, String> {
+        let program = super::parse(text)?;
+        for (i, instruction) in program.iter().enumerate() {
+            if !self.opcodes().contains(&instruction.opcode()) {
+                return Err(format!(
+                    "line {}: `{}` is not a {} instruction",
+                    i + 1,
+                    instruction,
+                    self.name()
+                ));
+            }
+        }
+        Ok(program)
+    }
+}
+
+/// The condition of a `while` or `if`.
+enum Test {
+    Always,
+    Never,
+    /// The jump of the dialect would be taken.
+    Holds(Value),
+    /// The jump of the dialect would not be taken.
+    Fails(Value),
+}
+
+enum Target {
+    Label(String),
+    /// A label, or else a register holding the offset.
+    Name(String),
+    Offset(Value),
+    /// Just past the last instruction.
+    Halt,
+}
+
+/// An instruction, or a jump whose offset is known once every label is.
+enum Pending {
+    Instruction(Instruction),
+    Jump {
+        condition: Value,
+        target: Target,
+        line: usize,
+    },
+}
+
+enum Block {
+    While {
+        top: String,
+        end: String,
+    },
+    If {
+        otherwise: String,
+        end: String,
+        has_else: bool,
+    },
+}
+
+struct Assembler {
+    dialect: Dialect,
+    constants: HashMap<String, Number>,
+    labels: HashMap<String, usize>,
+    /// The names used as registers so far.
+    registers: HashSet<String>,
+    code: Vec<Pending>,
+    /// The blocks that are open, along with the line they started on.
+    blocks: Vec<(Block, usize)>,
+    /// The number of labels made up for blocks so far.
+    generated: usize,
+    line: usize,
+}
+
+fn is_name(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Assembler {
+    fn error<T>(&self, message: String) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, message))
+    }
+
+    fn new_label(&mut self) -> String {
+        self.generated += 1;
+        // Not a valid name, so it cannot clash with the labels of the source.
+        format!("%{}", self.generated)
+    }
+
+    fn place(&mut self, label: String) -> Result<(), String> {
+        if self.labels.contains_key(&label) {
+            return self.error(format!("label {} is defined twice", label));
+        }
+        self.labels.insert(label, self.code.len());
+        Ok(())
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.code.push(Pending::Instruction(instruction));
+    }
+
+    fn jump(&mut self, condition: Value, target: Target) {
+        self.code.push(Pending::Jump {
+            condition,
+            target,
+            line: self.line,
+        });
+    }
+
+    /// Checks that a word can name a label or a constant.
+    fn check_name(&self, word: &str) -> Result<(), String> {
+        if !is_name(word) {
+            self.error(format!("`{}` is not a valid name", word))
+        } else if self.registers.contains(word) {
+            self.error(format!("`{}` is a register", word))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn register(&mut self, word: &str) -> Result<Register, String> {
+        if self.constants.contains_key(word) {
+            return self.error(format!("`{}` is a constant", word));
+        }
+        if self.labels.contains_key(word) {
+            return self.error(format!("`{}` is a label", word));
+        }
+        match Register::new(word) {
+            Some(r) => {
+                self.registers.insert(word.to_string());
+                Ok(r)
+            }
+            None => self.error(format!("`{}` is not a valid register", word)),
+        }
+    }
+
+    fn value(&mut self, word: &str) -> Result<Value, String> {
+        if let Ok(n) = word.parse() {
+            Ok(Value::Literal(n))
+        } else if let Some(&n) = self.constants.get(word) {
+            Ok(Value::Literal(n))
+        } else {
+            self.register(word).map(Value::Register)
+        }
+    }
+
+    fn target(&mut self, word: &str) -> Result<Target, String> {
+        if word.parse::<Number>().is_ok() || self.constants.contains_key(word) {
+            self.value(word).map(Target::Offset)
+        } else if is_name(word) {
+            Ok(Target::Name(word.to_string()))
+        } else {
+            self.error(format!("`{}` is not a label or a register", word))
+        }
+    }
+
+    fn test(&mut self, words: &[&str]) -> Result<Test, String> {
+        let test = match (self.dialect, words) {
+            (_, ["true"]) => Test::Always,
+            (_, ["false"]) => Test::Never,
+            (Dialect::Day18, [x, ">", "0"]) => Test::Holds(self.value(x)?),
+            (Dialect::Day18, [x, "<=", "0"]) => Test::Fails(self.value(x)?),
+            (Dialect::Day23, [x, "!=", "0"]) => Test::Holds(self.value(x)?),
+            (Dialect::Day23, [x, "==", "0"]) => Test::Fails(self.value(x)?),
+            (Dialect::Day18, _) => {
+                return self.error(String::from(
+                    "day18 conditions are `x > 0`, `x <= 0`, `true` or `false`",
+                ))
+            }
+            (Dialect::Day23, _) => {
+                return self.error(String::from(
+                    "day23 conditions are `x != 0`, `x == 0`, `true` or `false`",
+                ))
+            }
+        };
+        Ok(test)
+    }
+
+    /// Jumps to `label` unless `test` holds.
+    fn branch_unless(&mut self, test: Test, label: &str) {
+        match test {
+            Test::Always => {}
+            Test::Never => self.jump(Value::Literal(1), Target::Label(label.to_string())),
+            Test::Holds(x) => {
+                self.jump(x, Target::Offset(Value::Literal(2)));
+                self.jump(Value::Literal(1), Target::Label(label.to_string()));
+            }
+            Test::Fails(x) => self.jump(x, Target::Label(label.to_string())),
+        }
+    }
+
+    /// The innermost loop, for `break` and `continue`.
+    fn innermost_loop(&self) -> Result<(String, String), String> {
+        for (block, _) in self.blocks.iter().rev() {
+            if let Block::While { top, end } = block {
+                return Ok((top.clone(), end.clone()));
+            }
+        }
+        self.error(String::from("not inside a loop"))
+    }
+
+    fn instruction(&mut self, opcode: &str, operands: &[&str]) -> Result<(), String> {
+        if !self.dialect.opcodes().contains(&opcode) {
+            return self.error(format!(
+                "`{}` is not a {} instruction",
+                opcode,
+                self.dialect.name()
+            ));
+        }
+        let instruction = match (opcode, operands) {
+            ("snd", [v]) => Instruction::Snd(self.value(v)?),
+            ("rcv", [r]) => Instruction::Rcv(self.register(r)?),
+            ("set", [r, v]) => Instruction::Set(self.register(r)?, self.value(v)?),
+            ("add", [r, v]) => Instruction::Add(self.register(r)?, self.value(v)?),
+            ("sub", [r, v]) => Instruction::Sub(self.register(r)?, self.value(v)?),
+            ("mul", [r, v]) => Instruction::Mul(self.register(r)?, self.value(v)?),
+            ("mod", [r, v]) => Instruction::Mod(self.register(r)?, self.value(v)?),
+            ("jgz", [v, t]) | ("jnz", [v, t]) => {
+                let (condition, target) = (self.value(v)?, self.target(t)?);
+                self.jump(condition, target);
+                return Ok(());
+            }
+            _ => return self.error(format!("wrong number of operands for {}", opcode)),
+        };
+        self.emit(instruction);
+        Ok(())
+    }
+
+    fn statement(&mut self, words: &[&str]) -> Result<(), String> {
+        match words {
+            ["const", name, "=", value] => {
+                self.check_name(name)?;
+                if self.constants.contains_key(*name) {
+                    return self.error(format!("constant {} is defined twice", name));
+                }
+                match self.value(value)? {
+                    Value::Literal(n) => self.constants.insert(name.to_string(), n),
+                    Value::Register(_) => return self.error(String::from("not a constant")),
+                };
+            }
+            [label] if label.ends_with(':') => {
+                let label = &label[..label.len() - 1];
+                self.check_name(label)?;
+                self.place(label.to_string())?;
+            }
+            ["while", test @ .., "{"] => {
+                let test = self.test(test)?;
+                let (top, end) = (self.new_label(), self.new_label());
+                self.place(top.clone())?;
+                self.branch_unless(test, &end);
+                self.blocks.push((Block::While { top, end }, self.line));
+            }
+            ["if", test @ .., "{"] => {
+                let test = self.test(test)?;
+                let (otherwise, end) = (self.new_label(), self.new_label());
+                self.branch_unless(test, &otherwise);
+                let block = Block::If {
+                    otherwise,
+                    end,
+                    has_else: false,
+                };
+                self.blocks.push((block, self.line));
+            }
+            ["}", "else", "{"] => match self.blocks.pop() {
+                Some((
+                    Block::If {
+                        otherwise,
+                        end,
+                        has_else: false,
+                    },
+                    line,
+                )) => {
+                    self.jump(Value::Literal(1), Target::Label(end.clone()));
+                    self.place(otherwise.clone())?;
+                    let block = Block::If {
+                        otherwise,
+                        end,
+                        has_else: true,
+                    };
+                    self.blocks.push((block, line));
+                }
+                _ => return self.error(String::from("`else` without `if`")),
+            },
+            ["}"] => match self.blocks.pop() {
+                Some((Block::While { top, end }, _)) => {
+                    self.jump(Value::Literal(1), Target::Label(top));
+                    self.place(end)?;
+                }
+                Some((
+                    Block::If {
+                        otherwise,
+                        end,
+                        has_else,
+                    },
+                    _,
+                )) => {
+                    if !has_else {
+                        self.place(otherwise)?;
+                    }
+                    self.place(end)?;
+                }
+                None => return self.error(String::from("`}` without a block")),
+            },
+            ["break"] => {
+                let (_, end) = self.innermost_loop()?;
+                self.jump(Value::Literal(1), Target::Label(end));
+            }
+            ["continue"] => {
+                let (top, _) = self.innermost_loop()?;
+                self.jump(Value::Literal(1), Target::Label(top));
+            }
+            ["halt"] => self.jump(Value::Literal(1), Target::Halt),
+            ["goto", label] => {
+                self.check_name(label)?;
+                self.jump(Value::Literal(1), Target::Label(label.to_string()));
+            }
+            [opcode, operands @ ..] => self.instruction(opcode, operands)?,
+            [] => {}
+        }
+        Ok(())
+    }
+
+    /// Replaces the labels of the jumps by offsets.
+    fn resolve(self) -> Result<Vec<Instruction>, String> {
+        let len = self.code.len() as Number;
+        let dialect = self.dialect;
+        let labels = self.labels;
+        self.code
+            .into_iter()
+            .enumerate()
+            .map(|(i, pending)| match pending {
+                Pending::Instruction(instruction) => Ok(instruction),
+                Pending::Jump {
+                    condition,
+                    target,
+                    line,
+                } => {
+                    let offset = match target {
+                        Target::Offset(offset) => offset,
+                        Target::Halt => Value::Literal(len - i as Number),
+                        Target::Label(label) => match labels.get(&label) {
+                            Some(&t) => Value::Literal(t as Number - i as Number),
+                            None => return Err(format!("line {}: unknown label {}", line, label)),
+                        },
+                        Target::Name(name) => match (labels.get(&name), Register::new(&name)) {
+                            (Some(&t), _) => Value::Literal(t as Number - i as Number),
+                            (None, Some(r)) => Value::Register(r),
+                            (None, None) => {
+                                return Err(format!("line {}: unknown label {}", line, name))
+                            }
+                        },
+                    };
+                    Ok(dialect.jump(condition, offset))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Assembles a program for the given dialect.
+pub fn assemble(source: &str, dialect: Dialect) -> Result<Vec<Instruction>, String> {
+    let mut assembler = Assembler {
+        dialect,
+        constants: HashMap::new(),
+        labels: HashMap::new(),
+        registers: HashSet::new(),
+        code: Vec::new(),
+        blocks: Vec::new(),
+        generated: 0,
+        line: 0,
+    };
+
+    for (i, line) in source.lines().enumerate() {
+        assembler.line = i + 1;
+        let code = match line.find(['#', ';']) {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let code = code.replace('{', " { ").replace('}', " } ");
+        let words: Vec<&str> = code.split_whitespace().collect();
+        assembler.statement(&words)?;
+    }
+    if let Some((_, line)) = assembler.blocks.last() {
+        return Err(format!("line {}: block is never closed", line));
+    }
+    assembler.resolve()
+}
+
+/// The text of an assembled program, after checking that it reads back as
+/// the same instructions of the dialect.
+pub fn to_text(program: &[Instruction], dialect: Dialect) -> Result<String, String> {
+    let text: String = program.iter().map(|i| format!("{}\n", i)).collect();
+    if dialect.parse_program(&text)? != program {
+        return Err(format!(
+            "the {} parser does not read the program back the same",
+            dialect.name()
+        ));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duet::bytecode::{Event, Machine, Program};
+
+    const DAY18: &str = "
+const LIMIT = 3
+    set count LIMIT
+    while count > 0 {
+        snd count
+        add count -1
+    }
+    set skip 2
+    jgz 1 skip          # a register offset
+    snd 99
+    if count <= 0 {
+        goto done
+    }
+    snd 98
+done:
+    snd 7
+";
+
+    const DAY23: &str = "
+    set n 10
+    while n != 0 {
+        sub total -2
+        sub n 1
+        if n == 0 {
+            break
+        }
+    }
+    mul total total
+";
+
+    /// Assembles `source`, and checks that the text it turns into parses back
+    /// as the same instructions.
+    fn round_trip(source: &str, dialect: Dialect) -> Vec<Instruction> {
+        let program = assemble(source, dialect).unwrap();
+        let text = to_text(&program, dialect).unwrap();
+        assert!(super::super::parse(&text).unwrap() == program);
+        assert!(dialect.parse_program(&text).unwrap() == program);
+        program
+    }
+
+    fn register(name: &str) -> Register {
+        Register::new(name).unwrap()
+    }
+
+    #[test]
+    fn day18_round_trip() {
+        let program = Program::compile(&round_trip(DAY18, Dialect::Day18));
+        let mut machine = Machine::new(&program);
+        let mut sent = Vec::new();
+        loop {
+            match machine.run() {
+                Event::Sent(v) => sent.push(v),
+                Event::Halted => break,
+                _ => panic!("the program should halt"),
+            }
+        }
+        assert_eq!(sent, [3, 2, 1, 7]);
+        assert_eq!(machine.get(register("count")), 0);
+    }
+
+    #[test]
+    fn day23_round_trip() {
+        let program = Program::compile(&round_trip(DAY23, Dialect::Day23));
+        let mut machine = Machine::new(&program);
+        assert!(matches!(machine.run(), Event::Halted));
+        assert_eq!(machine.get(register("total")), 400);
+        assert_eq!(machine.muls, 1);
+    }
+
+    #[test]
+    fn names_are_registers_constants_or_labels() {
+        let error = |source| assemble(source, Dialect::Day18).err().unwrap();
+        assert_eq!(error("const n = 1\nset n 2"), "line 2: `n` is a constant");
+        assert_eq!(error("set top 1\ntop:"), "line 2: `top` is a register");
+        assert_eq!(error("top:\nset top 1"), "line 2: `top` is a label");
+        assert_eq!(error("goto nowhere"), "line 1: unknown label nowhere");
+        assert_eq!(error("sub x 1"), "line 1: `sub` is not a day18 instruction");
+    }
+}
@@ -0,0 +1,638 @@
+//! Programs compiled to a flat list of operations on a fixed set of
+//! registers, with every operand and jump target resolved up front.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Condition, Instruction, Number, Register, Value};
+use crate::bigint::BigInt;
+
+/// What arithmetic on 64-bit registers does with results that do not fit.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Arithmetic {
+    /// Stops the program with `Fault::Overflow`.
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+impl Arithmetic {
+    fn apply(
+        self,
+        a: Number,
+        b: Number,
+        checked: fn(Number, Number) -> Option<Number>,
+        wrapping: fn(Number, Number) -> Number,
+        saturating: fn(Number, Number) -> Number,
+    ) -> Result<Number, Fault> {
+        match self {
+            Arithmetic::Checked => checked(a, b).ok_or(Fault::Overflow),
+            Arithmetic::Wrapping => Ok(wrapping(a, b)),
+            Arithmetic::Saturating => Ok(saturating(a, b)),
+        }
+    }
+}
+
+impl FromStr for Arithmetic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Arithmetic, String> {
+        match s {
+            "checked" => Ok(Arithmetic::Checked),
+            "wrapping" => Ok(Arithmetic::Wrapping),
+            "saturating" => Ok(Arithmetic::Saturating),
+            _ => Err(format!("unknown arithmetic: {}", s)),
+        }
+    }
+}
+
+/// Why a program stopped before halting. The machine stays at the
+/// instruction that failed.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Fault {
+    Overflow,
+    ModuloByZero,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::Overflow => write!(f, "arithmetic overflow"),
+            Fault::ModuloByZero => write!(f, "modulo by zero"),
+        }
+    }
+}
+
+/// The value of a register. `mod` computes the remainder with the sign of
+/// the dividend, like `%`, so `-7 mod 2` is `-1` and `7 mod -2` is `1`.
+pub trait Word: Clone + PartialEq + fmt::Display {
+    fn from_number(n: Number) -> Self;
+    /// The value as a jump offset, or `None` if it is too far to stay in
+    /// any program.
+    fn to_number(&self) -> Option<Number>;
+    fn is_positive(&self) -> bool;
+    fn is_zero(&self) -> bool;
+    fn add(&self, other: &Self, arithmetic: Arithmetic) -> Result<Self, Fault>;
+    fn sub(&self, other: &Self, arithmetic: Arithmetic) -> Result<Self, Fault>;
+    fn mul(&self, other: &Self, arithmetic: Arithmetic) -> Result<Self, Fault>;
+    fn rem(&self, other: &Self, arithmetic: Arithmetic) -> Result<Self, Fault>;
+}
+
+impl Word for Number {
+    fn from_number(n: Number) -> Number {
+        n
+    }
+
+    fn to_number(&self) -> Option<Number> {
+        Some(*self)
+    }
+
+    fn is_positive(&self) -> bool {
+        *self > 0
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    fn add(&self, other: &Number, arithmetic: Arithmetic) -> Result<Number, Fault> {
+        arithmetic.apply(
+            *self,
+            *other,
+            Number::checked_add,
+            Number::wrapping_add,
+            Number::saturating_add,
+        )
+    }
+
+    fn sub(&self, other: &Number, arithmetic: Arithmetic) -> Result<Number, Fault> {
+        arithmetic.apply(
+            *self,
+            *other,
+            Number::checked_sub,
+            Number::wrapping_sub,
+            Number::saturating_sub,
+        )
+    }
+
+    fn mul(&self, other: &Number, arithmetic: Arithmetic) -> Result<Number, Fault> {
+        arithmetic.apply(
+            *self,
+            *other,
+            Number::checked_mul,
+            Number::wrapping_mul,
+            Number::saturating_mul,
+        )
+    }
+
+    fn rem(&self, other: &Number, arithmetic: Arithmetic) -> Result<Number, Fault> {
+        if *other == 0 {
+            return Err(Fault::ModuloByZero);
+        }
+        // Only `MIN mod -1` overflows, and its remainder is exactly zero.
+        arithmetic.apply(
+            *self,
+            *other,
+            Number::checked_rem,
+            Number::wrapping_rem,
+            Number::wrapping_rem,
+        )
+    }
+}
+
+/// Registers of any size, which never overflow.
+impl Word for BigInt {
+    fn from_number(n: Number) -> BigInt {
+        BigInt::from(n)
+    }
+
+    fn to_number(&self) -> Option<Number> {
+        self.to_i64()
+    }
+
+    fn is_positive(&self) -> bool {
+        BigInt::is_positive(self)
+    }
+
+    fn is_zero(&self) -> bool {
+        BigInt::is_zero(self)
+    }
+
+    fn add(&self, other: &BigInt, _: Arithmetic) -> Result<BigInt, Fault> {
+        Ok(self + other)
+    }
+
+    fn sub(&self, other: &BigInt, _: Arithmetic) -> Result<BigInt, Fault> {
+        Ok(self - other)
+    }
+
+    fn mul(&self, other: &BigInt, _: Arithmetic) -> Result<BigInt, Fault> {
+        Ok(self * other)
+    }
+
+    fn rem(&self, other: &BigInt, _: Arithmetic) -> Result<BigInt, Fault> {
+        self.checked_rem(other).ok_or(Fault::ModuloByZero)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Operand {
+    Register(usize),
+    Immediate(Number),
+}
+
+/// The condition of a computed jump.
+#[derive(Clone, Copy)]
+enum Test {
+    Always,
+    Positive(usize),
+    NonZero(usize),
+}
+
+/// The operations, with separate variants for register (`R`) and immediate
+/// (`I`) operands so that the interpreter does not have to check.
+#[derive(Clone, Copy)]
+enum Op {
+    SndR(usize),
+    SndI(Number),
+    SetR(usize, usize),
+    SetI(usize, Number),
+    AddR(usize, usize),
+    AddI(usize, Number),
+    SubR(usize, usize),
+    SubI(usize, Number),
+    MulR(usize, usize),
+    MulI(usize, Number),
+    ModR(usize, usize),
+    ModI(usize, Number),
+    Rcv(usize),
+    Jump(usize),
+    JgzR(usize, usize),
+    JnzR(usize, usize),
+    /// A jump whose offset is in a register.
+    Computed(Test, Operand),
+    Nop,
+}
+
+/// A compiled program. Jumps out of the program go to `code.len()`, which
+/// halts it.
+pub struct Program {
+    code: Vec<Op>,
+    registers: Vec<Register>,
+}
+
+impl Program {
+    pub fn compile(instructions: &[Instruction]) -> Program {
+        let mut program = Program {
+            code: Vec::with_capacity(instructions.len()),
+            registers: Vec::new(),
+        };
+        for (i, instruction) in instructions.iter().enumerate() {
+            let op = program.compile_instruction(i, instruction, instructions.len());
+            program.code.push(op);
+        }
+        program
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    /// The registers the program uses, in the order of their first use.
+    pub fn registers(&self) -> &[Register] {
+        &self.registers
+    }
+
+    fn register_index(&self, r: Register) -> Option<usize> {
+        self.registers.iter().position(|&x| x == r)
+    }
+
+    fn register(&mut self, r: Register) -> usize {
+        match self.register_index(r) {
+            Some(i) => i,
+            None => {
+                self.registers.push(r);
+                self.registers.len() - 1
+            }
+        }
+    }
+
+    fn operand(&mut self, v: &Value) -> Operand {
+        match v {
+            Value::Register(r) => Operand::Register(self.register(*r)),
+            Value::Literal(n) => Operand::Immediate(*n),
+        }
+    }
+
+    fn compile_instruction(&mut self, index: usize, instruction: &Instruction, len: usize) -> Op {
+        let target = |offset: Number| match (index as Number).checked_add(offset) {
+            Some(t) if t >= 0 && (t as usize) < len => t as usize,
+            _ => len,
+        };
+
+        match instruction {
+            Instruction::Snd(v) => match self.operand(v) {
+                Operand::Register(r) => Op::SndR(r),
+                Operand::Immediate(n) => Op::SndI(n),
+            },
+            Instruction::Set(r, v) => {
+                let r = self.register(*r);
+                match self.operand(v) {
+                    Operand::Register(s) => Op::SetR(r, s),
+                    Operand::Immediate(n) => Op::SetI(r, n),
+                }
+            }
+            Instruction::Add(r, v) => {
+                let r = self.register(*r);
+                match self.operand(v) {
+                    Operand::Register(s) => Op::AddR(r, s),
+                    Operand::Immediate(n) => Op::AddI(r, n),
+                }
+            }
+            Instruction::Sub(r, v) => {
+                let r = self.register(*r);
+                match self.operand(v) {
+                    Operand::Register(s) => Op::SubR(r, s),
+                    Operand::Immediate(n) => Op::SubI(r, n),
+                }
+            }
+            Instruction::Mul(r, v) => {
+                let r = self.register(*r);
+                match self.operand(v) {
+                    Operand::Register(s) => Op::MulR(r, s),
+                    Operand::Immediate(n) => Op::MulI(r, n),
+                }
+            }
+            Instruction::Mod(r, v) => {
+                let r = self.register(*r);
+                match self.operand(v) {
+                    Operand::Register(s) => Op::ModR(r, s),
+                    Operand::Immediate(n) => Op::ModI(r, n),
+                }
+            }
+            Instruction::Rcv(r) => Op::Rcv(self.register(*r)),
+            Instruction::Jgz(..) | Instruction::Jnz(..) => {
+                let (condition, offset) = instruction.jump().unwrap();
+                match (condition, offset) {
+                    (Condition::Never, _) => Op::Nop,
+                    (_, Value::Register(_)) => {
+                        let test = match condition {
+                            Condition::Positive(r) => Test::Positive(self.register(r)),
+                            Condition::NonZero(r) => Test::NonZero(self.register(r)),
+                            _ => Test::Always,
+                        };
+                        Op::Computed(test, self.operand(offset))
+                    }
+                    (Condition::Always, Value::Literal(n)) => Op::Jump(target(*n)),
+                    (Condition::Positive(r), Value::Literal(n)) => {
+                        Op::JgzR(self.register(r), target(*n))
+                    }
+                    (Condition::NonZero(r), Value::Literal(n)) => {
+                        Op::JnzR(self.register(r), target(*n))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Why a machine stopped running.
+pub enum Event<W = Number> {
+    /// The program sent a value and continues after the `snd`.
+    Sent(W),
+    /// The program is at a `rcv` of a register that currently holds the
+    /// given value. Continue with `receive` or `skip`.
+    Receiving(W),
+    Halted,
+    /// An instruction failed, and the program cannot continue.
+    Fault(Fault),
+}
+
+/// The state of one running program, with registers of type `W`.
+pub struct Machine<'a, W = Number> {
+    program: &'a Program,
+    pc: usize,
+    registers: Vec<W>,
+    arithmetic: Arithmetic,
+    /// The number of operations executed so far.
+    pub steps: u64,
+    /// The number of multiplications executed so far.
+    pub muls: u64,
+}
+
+impl<'a> Machine<'a> {
+    /// A machine with 64-bit registers and checked arithmetic.
+    pub fn new(program: &'a Program) -> Machine<'a> {
+        Machine::with_arithmetic(program, Arithmetic::Checked)
+    }
+}
+
+impl<'a, W: Word> Machine<'a, W> {
+    /// A machine whose 64-bit registers use the given arithmetic. Registers
+    /// of other types ignore it.
+    pub fn with_arithmetic(program: &'a Program, arithmetic: Arithmetic) -> Machine<'a, W> {
+        Machine {
+            program,
+            pc: 0,
+            registers: vec![W::from_number(0); program.registers.len()],
+            arithmetic,
+            steps: 0,
+            muls: 0,
+        }
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn get(&self, r: Register) -> W {
+        match self.program.register_index(r) {
+            Some(i) => self.registers[i].clone(),
+            None => W::from_number(0),
+        }
+    }
+
+    /// Sets a register. Registers the program does not use are ignored.
+    pub fn set(&mut self, r: Register, value: W) {
+        if let Some(i) = self.program.register_index(r) {
+            self.registers[i] = value;
+        }
+    }
+
+    /// Completes a `rcv` by storing the received value, and returns the
+    /// register it was stored in.
+    pub fn receive(&mut self, value: W) -> Option<Register> {
+        match self.program.code.get(self.pc) {
+            Some(&Op::Rcv(r)) => {
+                self.registers[r] = value;
+                self.pc += 1;
+                self.steps += 1;
+                Some(self.program.registers[r])
+            }
+            _ => None,
+        }
+    }
+
+    /// Moves past a `rcv` without receiving anything.
+    pub fn skip(&mut self) {
+        self.pc += 1;
+        self.steps += 1;
+    }
+
+    /// Continues at the given instruction. Going past the end halts.
+    pub fn jump(&mut self, pc: usize) {
+        self.pc = pc.min(self.program.code.len());
+    }
+
+    /// Runs one instruction, and returns the event that stopped the machine
+    /// if there was one.
+    pub fn step(&mut self) -> Option<Event<W>> {
+        self.execute(1, &mut |_, _| {})
+    }
+
+    /// Runs until the program sends, receives, halts or fails.
+    pub fn run(&mut self) -> Event<W> {
+        self.run_traced(|_, _| {})
+    }
+
+    /// Like `run`, but calls `trace` after every instruction with its index
+    /// and the register it wrote, if any, with the new value.
+    pub fn run_traced<F>(&mut self, mut trace: F) -> Event<W>
+    where
+        F: FnMut(usize, Option<(Register, &W)>),
+    {
+        loop {
+            if let Some(event) = self.execute(u64::MAX, &mut trace) {
+                return event;
+            }
+        }
+    }
+
+    /// Runs at most `limit` instructions.
+    fn execute<F>(&mut self, limit: u64, trace: &mut F) -> Option<Event<W>>
+    where
+        F: FnMut(usize, Option<(Register, &W)>),
+    {
+        let code = &self.program.code;
+        let names = &self.program.registers;
+        let arithmetic = self.arithmetic;
+        let r = &mut self.registers;
+        let mut pc = self.pc;
+        let mut steps = 0;
+        let mut muls = 0;
+
+        let event = loop {
+            if steps == limit {
+                break None;
+            }
+            let op = match code.get(pc) {
+                Some(op) => *op,
+                None => break Some(Event::Halted),
+            };
+            let at = pc;
+            pc += 1;
+            let result = match op {
+                Op::SndR(_) | Op::SndI(_) => {
+                    steps += 1;
+                    trace(at, None);
+                    break Some(Event::Sent(match op {
+                        Op::SndR(a) => r[a].clone(),
+                        Op::SndI(n) => W::from_number(n),
+                        _ => unreachable!(),
+                    }));
+                }
+                Op::SetR(a, b) => Some((a, Ok(r[b].clone()))),
+                Op::SetI(a, n) => Some((a, Ok(W::from_number(n)))),
+                Op::AddR(a, b) => Some((a, r[a].add(&r[b], arithmetic))),
+                Op::AddI(a, n) => Some((a, r[a].add(&W::from_number(n), arithmetic))),
+                Op::SubR(a, b) => Some((a, r[a].sub(&r[b], arithmetic))),
+                Op::SubI(a, n) => Some((a, r[a].sub(&W::from_number(n), arithmetic))),
+                Op::MulR(a, b) => Some((a, r[a].mul(&r[b], arithmetic))),
+                Op::MulI(a, n) => Some((a, r[a].mul(&W::from_number(n), arithmetic))),
+                Op::ModR(a, b) => Some((a, r[a].rem(&r[b], arithmetic))),
+                Op::ModI(a, n) => Some((a, r[a].rem(&W::from_number(n), arithmetic))),
+                Op::Rcv(a) => {
+                    pc = at;
+                    break Some(Event::Receiving(r[a].clone()));
+                }
+                Op::Jump(t) => {
+                    pc = t;
+                    None
+                }
+                Op::JgzR(a, t) => {
+                    if r[a].is_positive() {
+                        pc = t
+                    }
+                    None
+                }
+                Op::JnzR(a, t) => {
+                    if !r[a].is_zero() {
+                        pc = t
+                    }
+                    None
+                }
+                Op::Computed(test, offset) => {
+                    let taken = match test {
+                        Test::Always => true,
+                        Test::Positive(c) => r[c].is_positive(),
+                        Test::NonZero(c) => !r[c].is_zero(),
+                    };
+                    if taken {
+                        let offset = match offset {
+                            Operand::Register(b) => r[b].to_number(),
+                            Operand::Immediate(n) => Some(n),
+                        };
+                        let t = offset.and_then(|offset| offset.checked_add(at as Number));
+                        pc = match t {
+                            Some(t) if t >= 0 && (t as usize) < code.len() => t as usize,
+                            _ => code.len(),
+                        };
+                    }
+                    None
+                }
+                Op::Nop => None,
+            };
+
+            let written = match result {
+                None => None,
+                Some((a, Ok(value))) => {
+                    if let Op::MulR(..) | Op::MulI(..) = op {
+                        muls += 1;
+                    }
+                    r[a] = value;
+                    Some(a)
+                }
+                Some((_, Err(fault))) => {
+                    pc = at;
+                    break Some(Event::Fault(fault));
+                }
+            };
+            steps += 1;
+            trace(at, written.map(|a| (names[a], &r[a])));
+        };
+
+        self.pc = pc;
+        self.steps += steps;
+        self.muls += muls;
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duet::{self, Register};
+
+    /// How the program ends with the given arithmetic: the value of `a`, or
+    /// the fault and the instruction it stopped at.
+    fn outcome(source: &str, arithmetic: Arithmetic) -> Result<Number, (Fault, usize)> {
+        let program = Program::compile(&duet::parse(source).unwrap());
+        let mut machine = Machine::with_arithmetic(&program, arithmetic);
+        match machine.run() {
+            Event::Halted => Ok(machine.get(Register::new("a").unwrap())),
+            Event::Fault(fault) => Err((fault, machine.pc())),
+            Event::Sent(_) | Event::Receiving(_) => panic!("{} communicates", source),
+        }
+    }
+
+    #[test]
+    fn extreme_literals_fault_instead_of_panicking() {
+        use Arithmetic::*;
+        let (max, min) = (Number::MAX, Number::MIN);
+        let cases = [
+            (
+                "set a 9223372036854775807\nadd a 1",
+                Checked,
+                Err((Fault::Overflow, 1)),
+            ),
+            ("set a 9223372036854775807\nadd a 1", Wrapping, Ok(min)),
+            ("set a 9223372036854775807\nadd a 1", Saturating, Ok(max)),
+            (
+                "set a -9223372036854775808\nsub a 1",
+                Checked,
+                Err((Fault::Overflow, 1)),
+            ),
+            ("set a -9223372036854775808\nsub a 1", Wrapping, Ok(max)),
+            (
+                "set a -9223372036854775808\nmul a -1",
+                Checked,
+                Err((Fault::Overflow, 1)),
+            ),
+            ("set a -9223372036854775808\nmul a -1", Saturating, Ok(max)),
+            (
+                "set a -9223372036854775808\nmod a -1",
+                Checked,
+                Err((Fault::Overflow, 1)),
+            ),
+            ("set a -9223372036854775808\nmod a -1", Wrapping, Ok(0)),
+            ("set a -9223372036854775808\nmod a -1", Saturating, Ok(0)),
+            ("set a 1\nmod a 0", Wrapping, Err((Fault::ModuloByZero, 1))),
+            (
+                "set a -9223372036854775808\njgz 1 a\nset a 1",
+                Checked,
+                Ok(min),
+            ),
+        ];
+        for (source, arithmetic, expected) in cases.iter() {
+            assert!(outcome(source, *arithmetic) == *expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn extreme_jump_offsets_halt() {
+        for offset in [Number::MAX, Number::MIN].iter() {
+            for jump in ["jnz a", "jgz a", "jnz 1"].iter() {
+                let source = format!("set a 1\n{} {}\nset b 1\n", jump, offset);
+                let program = Program::compile(&duet::parse(&source).unwrap());
+                let mut machine = Machine::new(&program);
+                assert!(matches!(machine.run(), Event::Halted), "{}", source);
+                assert_eq!(machine.get(Register::new("b").unwrap()), 0);
+                assert_eq!(machine.pc(), program.len());
+            }
+        }
+    }
+}
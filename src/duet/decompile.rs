@@ -0,0 +1,322 @@
+use std::collections::BTreeSet;
+
+use super::{leaders, Condition, Instruction, Number, Value};
+
+fn condition(c: Condition, negate: bool) -> String {
+    match (c, negate) {
+        (Condition::Positive(r), false) => format!("{} > 0", r),
+        (Condition::Positive(r), true) => format!("{} <= 0", r),
+        (Condition::NonZero(r), false) => format!("{} != 0", r),
+        (Condition::NonZero(r), true) => format!("{} == 0", r),
+        (Condition::Always, false) | (Condition::Never, true) => String::from("true"),
+        (Condition::Always, true) | (Condition::Never, false) => String::from("false"),
+    }
+}
+
+fn statement(instruction: &Instruction) -> String {
+    match instruction {
+        Instruction::Snd(v) => format!("send({})", v),
+        Instruction::Set(r, v) => format!("{} = {}", r, v),
+        Instruction::Add(r, Value::Literal(n)) if *n < 0 => format!("{} -= {}", r, -n),
+        Instruction::Add(r, v) => format!("{} += {}", r, v),
+        Instruction::Sub(r, Value::Literal(n)) if *n < 0 => format!("{} += {}", r, -n),
+        Instruction::Sub(r, v) => format!("{} -= {}", r, v),
+        Instruction::Mul(r, v) => format!("{} *= {}", r, v),
+        Instruction::Mod(r, v) => format!("{} %= {}", r, v),
+        Instruction::Rcv(r) => format!("{} = receive()", r),
+        Instruction::Jgz(..) | Instruction::Jnz(..) => unreachable!(),
+    }
+}
+
+/// Turns the jumps of a program back into loops and conditionals. Loops are
+/// found from backward jumps, conditionals from forward jumps that stay
+/// within the enclosing block, and whatever does not fit either becomes a
+/// `break`, `continue`, `halt` or `goto`.
+struct Decompiler<'a> {
+    program: &'a [Instruction],
+    /// The targets of the gotos of the previous pass, which need a label.
+    labels: BTreeSet<usize>,
+    gotos: BTreeSet<usize>,
+    /// The first instruction of each enclosing loop and the one after it.
+    loops: Vec<(usize, usize)>,
+    output: String,
+}
+
+impl<'a> Decompiler<'a> {
+    fn line(&mut self, depth: usize, text: &str) {
+        self.output.push_str(&"    ".repeat(depth));
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    fn back_target(&self, index: usize) -> Option<usize> {
+        match self.program[index].jump() {
+            Some((Condition::Never, _)) => None,
+            _ => match self.program[index].jump_target(index) {
+                Some(t) if t >= 0 && t as usize <= index => Some(t as usize),
+                _ => None,
+            },
+        }
+    }
+
+    /// The target of an unconditional forward jump at `index`.
+    fn forward_target(&self, index: usize) -> Option<usize> {
+        match self.program[index].jump() {
+            Some((Condition::Always, _)) => match self.program[index].jump_target(index) {
+                Some(t) if t > index as Number => Some(t as usize),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether a jump from outside `lo..hi` lands inside it past `lo`, in
+    /// which case the instructions cannot be turned into a block, because
+    /// there would be no way to jump into the middle of it.
+    fn entered(&self, lo: usize, hi: usize) -> bool {
+        self.program.iter().enumerate().any(|(k, instruction)| {
+            if (lo..hi).contains(&k) || matches!(instruction.jump(), Some((Condition::Never, _))) {
+                return false;
+            }
+            match instruction.jump_target(k) {
+                Some(t) => t > lo as Number && t < hi as Number,
+                None => false,
+            }
+        })
+    }
+
+    fn jump(&mut self, target: Number) -> String {
+        if let Some(&(header, exit)) = self.loops.last() {
+            if target == exit as Number {
+                return String::from("break");
+            }
+            if target == header as Number {
+                return String::from("continue");
+            }
+        }
+        if target < 0 || target as usize >= self.program.len() {
+            return String::from("halt");
+        }
+        self.gotos.insert(target as usize);
+        format!("goto L{}", target)
+    }
+
+    fn block(&mut self, lo: usize, hi: usize, depth: usize, label_lo: bool) {
+        let mut i = lo;
+        while i < hi {
+            if self.labels.contains(&i) && (i != lo || label_lo) {
+                self.line(depth, &format!("L{}:", i));
+            }
+
+            let back_jump = (i..hi)
+                .rev()
+                .find(|&j| self.back_target(j) == Some(i) && !self.entered(i, j + 1));
+            if let Some(j) = back_jump {
+                let (c, _) = self.program[j].jump().unwrap();
+                self.line(
+                    depth,
+                    if c == Condition::Always {
+                        "loop {"
+                    } else {
+                        "do {"
+                    },
+                );
+                self.loops.push((i, j + 1));
+                self.block(i, j, depth + 1, false);
+                self.loops.pop();
+                if c == Condition::Always {
+                    self.line(depth, "}");
+                } else {
+                    self.line(depth, &format!("}} while ({});", condition(c, false)));
+                }
+                i = j + 1;
+                continue;
+            }
+
+            let (c, offset) = match self.program[i].jump() {
+                None => {
+                    let text = statement(&self.program[i]);
+                    self.line(depth, &text);
+                    i += 1;
+                    continue;
+                }
+                Some(jump) => jump,
+            };
+            let target = match offset {
+                // An offset too large to add leaves the program like -1.
+                Value::Literal(_) => self.program[i].jump_target(i).unwrap_or(-1),
+                Value::Register(r) => {
+                    let text = format!("goto {} + {}", i, r);
+                    match c {
+                        Condition::Always => self.line(depth, &text),
+                        Condition::Never => {}
+                        _ => self.line(depth, &format!("if ({}) {}", condition(c, false), text)),
+                    }
+                    i += 1;
+                    continue;
+                }
+            };
+
+            match c {
+                Condition::Never => i += 1,
+                Condition::Always => {
+                    let text = self.jump(target);
+                    self.line(depth, &text);
+                    i += 1;
+                }
+                _ if target > i as Number
+                    && target <= hi as Number
+                    && !self.entered(i, target as usize) =>
+                {
+                    let t = target as usize;
+                    let skip = if t == i + 2 {
+                        self.forward_target(i + 1)
+                            .filter(|&u| u > t && u <= hi && !self.entered(i, u))
+                    } else {
+                        None
+                    };
+                    let other = if t >= i + 2 {
+                        self.forward_target(t - 1)
+                            .filter(|&u| u > t && u <= hi && !self.entered(i, u))
+                    } else {
+                        None
+                    };
+                    if let Some(u) = skip {
+                        // `jnz x 2; jnz 1 n` runs what follows only if `x` is not zero.
+                        self.line(depth, &format!("if ({}) {{", condition(c, false)));
+                        self.block(t, u, depth + 1, true);
+                        self.line(depth, "}");
+                        i = u;
+                    } else if let Some(u) = other {
+                        self.line(depth, &format!("if ({}) {{", condition(c, true)));
+                        self.block(i + 1, t - 1, depth + 1, true);
+                        self.line(depth, "} else {");
+                        self.block(t, u, depth + 1, true);
+                        self.line(depth, "}");
+                        i = u;
+                    } else {
+                        self.line(depth, &format!("if ({}) {{", condition(c, true)));
+                        self.block(i + 1, t, depth + 1, true);
+                        self.line(depth, "}");
+                        i = t;
+                    }
+                }
+                _ => {
+                    let text = format!("if ({}) {}", condition(c, false), self.jump(target));
+                    self.line(depth, &text);
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Decompiles a program into structured pseudo-code.
+pub fn decompile(program: &[Instruction]) -> String {
+    let mut decompiler = Decompiler {
+        program,
+        labels: BTreeSet::new(),
+        gotos: BTreeSet::new(),
+        loops: Vec::new(),
+        output: String::new(),
+    };
+    // The first pass finds out which instructions need a label.
+    decompiler.block(0, program.len(), 0, true);
+    decompiler.labels = std::mem::take(&mut decompiler.gotos);
+    decompiler.output.clear();
+    decompiler.block(0, program.len(), 0, true);
+    decompiler.output
+}
+
+/// The control flow graph of a program in Graphviz DOT format, with one node
+/// per basic block.
+pub fn control_flow_graph(program: &[Instruction]) -> String {
+    let leaders: Vec<usize> = leaders(program).into_iter().collect();
+    let node = |index: Number| {
+        if index < 0 || index as usize >= program.len() {
+            String::from("end")
+        } else {
+            format!("b{}", index)
+        }
+    };
+
+    let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+    dot.push_str("    end [shape=doublecircle, label=\"end\"];\n");
+    let mut computed = false;
+
+    for (n, &start) in leaders.iter().enumerate() {
+        let end = leaders.get(n + 1).copied().unwrap_or(program.len());
+        let label: String = (start..end)
+            .map(|i| format!("{}: {}\\l", i, program[i]))
+            .collect();
+        dot.push_str(&format!("    b{} [label=\"{}\"];\n", start, label));
+
+        let last = end - 1;
+        let next = node(end as Number);
+        match program[last].jump() {
+            None | Some((Condition::Never, _)) => {
+                dot.push_str(&format!("    b{} -> {};\n", start, next));
+            }
+            Some((c, Value::Literal(_))) => {
+                let target = node(program[last].jump_target(last).unwrap_or(-1));
+                if c == Condition::Always {
+                    dot.push_str(&format!("    b{} -> {};\n", start, target));
+                } else {
+                    dot.push_str(&format!(
+                        "    b{} -> {} [label=\"{}\"];\n",
+                        start,
+                        target,
+                        condition(c, false)
+                    ));
+                    dot.push_str(&format!(
+                        "    b{} -> {} [label=\"{}\"];\n",
+                        start,
+                        next,
+                        condition(c, true)
+                    ));
+                }
+            }
+            Some((c, Value::Register(r))) => {
+                computed = true;
+                dot.push_str(&format!(
+                    "    b{} -> computed [style=dashed, label=\"+{}\"];\n",
+                    start, r
+                ));
+                if c != Condition::Always {
+                    dot.push_str(&format!(
+                        "    b{} -> {} [label=\"{}\"];\n",
+                        start,
+                        next,
+                        condition(c, true)
+                    ));
+                }
+            }
+        }
+    }
+
+    if computed {
+        dot.push_str("    computed [shape=diamond, label=\"computed jump\"];\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duet;
+
+    #[test]
+    fn extreme_jump_offsets_halt() {
+        let program =
+            duet::parse("set a 1\njnz a 9223372036854775807\nset b 1\njgz b -9223372036854775808")
+                .unwrap();
+        assert_eq!(
+            decompile(&program),
+            "a = 1\nif (a != 0) halt\nb = 1\nif (b > 0) halt\n"
+        );
+        let cfg = control_flow_graph(&program);
+        assert!(cfg.contains("b0 -> end [label=\"a != 0\"];"));
+        assert!(cfg.contains("b2 -> end [label=\"b > 0\"];"));
+    }
+}
@@ -0,0 +1,333 @@
+//! Any number of programs sending each other messages, each with register `p`
+//! set to its id.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::bytecode::{Event, Fault, Machine, Program};
+use super::trace::Record;
+use super::{Number, Register};
+
+/// Who receives the messages a program sends.
+pub enum Topology {
+    /// Every program sends to the next one, and the last one to the first.
+    Ring,
+    /// Programs 0 and 1 send to each other, as do 2 and 3, and so on.
+    Pairs,
+    /// Every program sends to all the others.
+    Broadcast,
+    /// The given edges from sender to receiver.
+    Custom(Vec<(usize, usize)>),
+}
+
+/// The form `Topology::parse` reads, without spaces.
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Topology::Ring => write!(f, "ring"),
+            Topology::Pairs => write!(f, "pairs"),
+            Topology::Broadcast => write!(f, "broadcast"),
+            Topology::Custom(edges) => {
+                let edges: Vec<String> = edges
+                    .iter()
+                    .map(|(from, to)| format!("{}>{}", from, to))
+                    .collect();
+                write!(f, "{}", edges.join(","))
+            }
+        }
+    }
+}
+
+impl Topology {
+    /// Parses `ring`, `pairs`, `broadcast` or a list of edges such as
+    /// `0>1,1>2,2>0`.
+    pub fn parse(s: &str) -> Result<Topology, String> {
+        match s {
+            "ring" => Ok(Topology::Ring),
+            "pairs" => Ok(Topology::Pairs),
+            "broadcast" => Ok(Topology::Broadcast),
+            _ => s
+                .split(',')
+                .map(|edge| {
+                    let mut ends = edge.splitn(2, '>').map(|e| e.trim().parse().ok());
+                    match (ends.next().flatten(), ends.next().flatten()) {
+                        (Some(from), Some(to)) => Ok((from, to)),
+                        _ => Err(format!("invalid edge: {}", edge)),
+                    }
+                })
+                .collect::<Result<Vec<(usize, usize)>, String>>()
+                .map(Topology::Custom),
+        }
+    }
+
+    /// The receivers of every program.
+    fn receivers(&self, count: usize) -> Result<Vec<Vec<usize>>, String> {
+        let mut receivers = vec![Vec::new(); count];
+        match self {
+            Topology::Ring => {
+                for (p, r) in receivers.iter_mut().enumerate() {
+                    r.push((p + 1) % count);
+                }
+            }
+            Topology::Pairs => {
+                if !count.is_multiple_of(2) {
+                    return Err(format!("cannot pair up {} programs", count));
+                }
+                for (p, r) in receivers.iter_mut().enumerate() {
+                    r.push(p ^ 1);
+                }
+            }
+            Topology::Broadcast => {
+                for (p, r) in receivers.iter_mut().enumerate() {
+                    r.extend((0..count).filter(|&q| q != p));
+                }
+            }
+            Topology::Custom(edges) => {
+                for &(from, to) in edges.iter() {
+                    if from >= count || to >= count {
+                        return Err(format!(
+                            "edge {}>{} is not between the {} programs",
+                            from, to, count
+                        ));
+                    }
+                    receivers[from].push(to);
+                }
+            }
+        }
+        Ok(receivers)
+    }
+}
+
+/// The order in which programs get a turn.
+pub enum Schedule {
+    /// Every program that can run gets a turn, in order of id.
+    Fair,
+    /// A random program that can run gets the next turn, with the given seed.
+    Random(u64),
+    /// The programs get turns in the given order, to replay a trace.
+    Replay(Vec<usize>),
+}
+
+impl Schedule {
+    /// Parses `fair`, `random` or `random:SEED`.
+    pub fn parse(s: &str) -> Result<Schedule, String> {
+        match s {
+            "fair" => Ok(Schedule::Fair),
+            "random" => Ok(Schedule::Random(1)),
+            _ => match s.strip_prefix("random:").map(|seed| seed.parse()) {
+                Some(Ok(seed)) => Ok(Schedule::Random(seed)),
+                _ => Err(format!("unknown schedule: {}", s)),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    Running,
+    Waiting,
+    Halted,
+    /// An instruction failed, which stopped the program for good.
+    Failed(Fault),
+}
+
+/// Why a network stopped.
+pub enum Outcome {
+    /// Every program has halted or failed.
+    Halted,
+    /// No program can run, and the given ones are waiting for a message.
+    Deadlock(Vec<usize>),
+    /// The limit on the number of turns was reached.
+    TurnLimit,
+}
+
+pub struct Network<'a> {
+    machines: Vec<Machine<'a>>,
+    status: Vec<Status>,
+    queues: Vec<VecDeque<Number>>,
+    receivers: Vec<Vec<usize>>,
+    pub sent: Vec<usize>,
+    pub received: Vec<usize>,
+    /// The number of instructions executed by all programs.
+    pub time: u64,
+}
+
+impl<'a> Network<'a> {
+    pub fn new(
+        program: &'a Program,
+        count: usize,
+        topology: &Topology,
+    ) -> Result<Network<'a>, String> {
+        let receivers = topology.receivers(count)?;
+        let machines = (0..count)
+            .map(|p| {
+                let mut machine = Machine::new(program);
+                machine.set(Register::new("p").unwrap(), p as Number);
+                machine
+            })
+            .collect();
+        Ok(Network {
+            machines,
+            status: vec![Status::Running; count],
+            queues: vec![VecDeque::new(); count],
+            receivers,
+            sent: vec![0; count],
+            received: vec![0; count],
+            time: 0,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    pub fn status(&self, p: usize) -> Status {
+        self.status[p]
+    }
+
+    pub fn queue(&self, p: usize) -> &VecDeque<Number> {
+        &self.queues[p]
+    }
+
+    pub fn machine(&self, p: usize) -> &Machine<'a> {
+        &self.machines[p]
+    }
+
+    fn can_run(&self, p: usize) -> bool {
+        match self.status[p] {
+            Status::Running => true,
+            Status::Waiting => !self.queues[p].is_empty(),
+            Status::Halted | Status::Failed(_) => false,
+        }
+    }
+
+    /// Runs program `p` until it sends, waits or halts, delivering a waiting
+    /// message first. A program that cannot run does nothing.
+    fn turn(&mut self, p: usize, mut trace: Option<&mut Vec<Record>>) {
+        if !self.can_run(p) {
+            return;
+        }
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.push(Record::Turn { program: p });
+        }
+
+        if self.status[p] == Status::Waiting {
+            let message = self.queues[p].pop_front().unwrap();
+            let pc = self.machines[p].pc();
+            let register = self.machines[p].receive(message);
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(Record::Execute {
+                    time: self.time,
+                    program: p,
+                    pc,
+                    write: register.map(|r| (r, message)),
+                });
+                trace.push(Record::Receive {
+                    time: self.time,
+                    program: p,
+                    value: message,
+                });
+            }
+            self.time += 1;
+            self.received[p] += 1;
+            self.status[p] = Status::Running;
+        }
+
+        let steps = self.machines[p].steps;
+        let event = match trace {
+            None => self.machines[p].run(),
+            Some(trace) => {
+                let mut time = self.time;
+                let event = self.machines[p].run_traced(|pc, write| {
+                    trace.push(Record::Execute {
+                        time,
+                        program: p,
+                        pc,
+                        write: write.map(|(r, v)| (r, *v)),
+                    });
+                    time += 1;
+                });
+                if let Event::Sent(value) = event {
+                    trace.push(Record::Send {
+                        time: time - 1,
+                        program: p,
+                        value,
+                    });
+                }
+                event
+            }
+        };
+        self.time += self.machines[p].steps - steps;
+
+        match event {
+            Event::Sent(message) => {
+                self.sent[p] += 1;
+                for &r in self.receivers[p].iter() {
+                    self.queues[r].push_back(message);
+                }
+            }
+            Event::Receiving(_) => self.status[p] = Status::Waiting,
+            Event::Halted => self.status[p] = Status::Halted,
+            Event::Fault(fault) => self.status[p] = Status::Failed(fault),
+        }
+    }
+
+    /// Runs the programs until none of them can run, or for at most
+    /// `max_turns` turns.
+    pub fn run(&mut self, schedule: &Schedule, max_turns: Option<u64>) -> Outcome {
+        self.run_traced(schedule, max_turns, None)
+    }
+
+    /// Like `run`, but records everything that happens in `trace`.
+    pub fn run_traced(
+        &mut self,
+        schedule: &Schedule,
+        max_turns: Option<u64>,
+        mut trace: Option<&mut Vec<Record>>,
+    ) -> Outcome {
+        let mut random = match schedule {
+            Schedule::Random(seed) => (*seed).max(1),
+            _ => 0,
+        };
+        let mut turns = 0;
+
+        loop {
+            let runnable: Vec<usize> = (0..self.len()).filter(|&p| self.can_run(p)).collect();
+            if runnable.is_empty() {
+                let waiting: Vec<usize> = (0..self.len())
+                    .filter(|&p| self.status[p] == Status::Waiting)
+                    .collect();
+                return if waiting.is_empty() {
+                    Outcome::Halted
+                } else {
+                    Outcome::Deadlock(waiting)
+                };
+            }
+
+            let chosen = match schedule {
+                Schedule::Fair => runnable,
+                Schedule::Random(_) => {
+                    random ^= random << 13;
+                    random ^= random >> 7;
+                    random ^= random << 17;
+                    vec![runnable[(random % runnable.len() as u64) as usize]]
+                }
+                Schedule::Replay(order) => match order.get(turns as usize) {
+                    Some(&p) if p < self.len() => vec![p],
+                    _ => return Outcome::TurnLimit,
+                },
+            };
+            for p in chosen {
+                if max_turns == Some(turns) {
+                    return Outcome::TurnLimit;
+                }
+                turns += 1;
+                self.turn(p, trace.as_deref_mut());
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Traces of network runs, with one record per executed instruction, message
+//! and turn. The time of a record counts the instructions executed by all
+//! programs before it, so traces of the same run are identical.
+
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use super::network::Topology;
+use super::{Number, Register};
+
+/// The first line of a trace file.
+const HEADER: &str = "duet-trace 1";
+
+#[derive(Clone, PartialEq)]
+pub enum Record {
+    /// The scheduler gave a turn to a program.
+    Turn { program: usize },
+    /// A program executed the instruction at `pc`, writing a register.
+    Execute {
+        time: u64,
+        program: usize,
+        pc: usize,
+        write: Option<(Register, Number)>,
+    },
+    Send {
+        time: u64,
+        program: usize,
+        value: Number,
+    },
+    Receive {
+        time: u64,
+        program: usize,
+        value: Number,
+    },
+}
+
+impl Record {
+    fn to_line(&self) -> String {
+        match self {
+            Record::Turn { program } => format!("turn {}", program),
+            Record::Execute {
+                time,
+                program,
+                pc,
+                write: Some((r, v)),
+            } => format!("exec {} {} {} {}={}", time, program, pc, r, v),
+            Record::Execute {
+                time, program, pc, ..
+            } => format!("exec {} {} {}", time, program, pc),
+            Record::Send {
+                time,
+                program,
+                value,
+            } => format!("send {} {} {}", time, program, value),
+            Record::Receive {
+                time,
+                program,
+                value,
+            } => format!("recv {} {} {}", time, program, value),
+        }
+    }
+
+    fn from_line(line: &str) -> Option<Record> {
+        let fields: Vec<&str> = line.split(' ').collect();
+        let number = |i: usize| fields.get(i).and_then(|f| f.parse::<u64>().ok());
+        match fields[0] {
+            "turn" => Some(Record::Turn {
+                program: number(1)? as usize,
+            }),
+            "exec" => {
+                let write = match fields.get(4) {
+                    None => None,
+                    Some(w) => {
+                        let mut parts = w.splitn(2, '=');
+                        let r = Register::new(parts.next()?)?;
+                        let v = parts.next()?.parse().ok()?;
+                        Some((r, v))
+                    }
+                };
+                Some(Record::Execute {
+                    time: number(1)?,
+                    program: number(2)? as usize,
+                    pc: number(3)? as usize,
+                    write,
+                })
+            }
+            "send" | "recv" => {
+                let time = number(1)?;
+                let program = number(2)? as usize;
+                let value = fields.get(3)?.parse().ok()?;
+                Some(if fields[0] == "send" {
+                    Record::Send {
+                        time,
+                        program,
+                        value,
+                    }
+                } else {
+                    Record::Receive {
+                        time,
+                        program,
+                        value,
+                    }
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A recorded run, along with how the network was set up.
+pub struct Trace {
+    pub programs: usize,
+    pub topology: Topology,
+    pub records: Vec<Record>,
+}
+
+impl Trace {
+    /// The programs given a turn, in order, for replaying the run.
+    pub fn turns(&self) -> Vec<usize> {
+        self.records
+            .iter()
+            .filter_map(|r| match r {
+                Record::Turn { program } => Some(*program),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        writeln!(
+            file,
+            "{} programs={} topology={}",
+            HEADER, self.programs, self.topology
+        )?;
+        for record in self.records.iter() {
+            writeln!(file, "{}", record.to_line())?;
+        }
+        file.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Trace> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        let header = lines.next().unwrap_or("");
+        if !header.starts_with(HEADER) {
+            return Err(invalid(format!("{} is not a trace", path.display())));
+        }
+
+        let mut programs = None;
+        let mut topology = None;
+        for field in header[HEADER.len()..].split_whitespace() {
+            if let Some(n) = field.strip_prefix("programs=") {
+                programs = n.parse().ok();
+            } else if let Some(t) = field.strip_prefix("topology=") {
+                topology = Topology::parse(t).ok();
+            }
+        }
+
+        let records = lines
+            .enumerate()
+            .map(|(i, line)| {
+                Record::from_line(line)
+                    .ok_or_else(|| invalid(format!("line {}: invalid record `{}`", i + 2, line)))
+            })
+            .collect::<io::Result<Vec<Record>>>()?;
+
+        match (programs, topology) {
+            (Some(programs), Some(topology)) => Ok(Trace {
+                programs,
+                topology,
+                records,
+            }),
+            _ => Err(invalid(format!("{}: invalid header", path.display()))),
+        }
+    }
+}
+
+/// The index of the first record that differs between two traces, or `None`
+/// if they are identical. If one trace is a prefix of the other, they diverge
+/// where the shorter one ends.
+pub fn first_divergence(a: &[Record], b: &[Record]) -> Option<usize> {
+    match a.iter().zip(b.iter()).position(|(x, y)| x != y) {
+        Some(i) => Some(i),
+        None if a.len() != b.len() => Some(a.len().min(b.len())),
+        None => None,
+    }
+}
+
+/// The records around `index`, for showing a divergence.
+pub fn context(records: &[Record], index: usize, before: usize) -> Vec<String> {
+    let start = index.saturating_sub(before);
+    (start..=index)
+        .map(|i| match records.get(i) {
+            Some(r) => format!("{:>8}  {}", i, r.to_line()),
+            None => format!("{:>8}  <end of trace>", i),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn topology_with_spaces_round_trips() {
+        let trace = Trace {
+            programs: 2,
+            topology: Topology::parse("0>1, 1>0").unwrap(),
+            records: vec![
+                Record::Turn { program: 0 },
+                Record::Execute {
+                    time: 0,
+                    program: 0,
+                    pc: 0,
+                    write: Some((Register::new("a").unwrap(), 1)),
+                },
+                Record::Send {
+                    time: 1,
+                    program: 0,
+                    value: 1,
+                },
+            ],
+        };
+        let path = env::temp_dir().join(format!("duet-trace-{}", process::id()));
+        trace.save(&path).unwrap();
+        let loaded = Trace::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.programs, 2);
+        assert_eq!(loaded.topology.to_string(), "0>1,1>0");
+        assert!(loaded.records == trace.records);
+    }
+}
@@ -0,0 +1,385 @@
+//! Turns a program into Rust source: registers become locals and the jumps a
+//! `match` on the program counter inside a labelled loop, with one arm per
+//! basic block.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{leaders, Condition, Instruction, Number, Register, Value};
+
+/// The registers a program uses, sorted by name.
+fn registers(program: &[Instruction]) -> Vec<Register> {
+    let mut registers = BTreeSet::new();
+    let mut add = |v: &Value| {
+        if let Value::Register(r) = v {
+            registers.insert(*r);
+        }
+    };
+    for instruction in program.iter() {
+        match instruction {
+            Instruction::Snd(v) => add(v),
+            Instruction::Rcv(r) => add(&Value::Register(*r)),
+            Instruction::Set(r, v)
+            | Instruction::Add(r, v)
+            | Instruction::Sub(r, v)
+            | Instruction::Mul(r, v)
+            | Instruction::Mod(r, v) => {
+                add(&Value::Register(*r));
+                add(v);
+            }
+            Instruction::Jgz(v1, v2) | Instruction::Jnz(v1, v2) => {
+                add(v1);
+                add(v2);
+            }
+        }
+    }
+    registers.into_iter().collect()
+}
+
+fn local(r: Register) -> String {
+    format!("r_{}", r)
+}
+
+fn value(v: &Value) -> String {
+    match v {
+        Value::Register(r) => local(*r),
+        Value::Literal(n) => n.to_string(),
+    }
+}
+
+fn condition(c: Condition) -> String {
+    match c {
+        Condition::Positive(r) => format!("{} > 0", local(r)),
+        Condition::NonZero(r) => format!("{} != 0", local(r)),
+        Condition::Always => String::from("true"),
+        Condition::Never => String::from("false"),
+    }
+}
+
+/// The code of the basic block `start..end`.
+fn block(program: &[Instruction], start: usize, end: usize) -> String {
+    let len = program.len() as Number;
+    let target = |t: Number| {
+        if t < 0 || t >= len {
+            len
+        } else {
+            t
+        }
+    };
+
+    let mut code = String::new();
+    let mut falls_through = true;
+    for (i, instruction) in program.iter().enumerate().take(end).skip(start) {
+        let line = match instruction {
+            Instruction::Snd(v) => format!("channel.send({});", value(v)),
+            Instruction::Set(r, v) => format!("{} = {};", local(*r), value(v)),
+            Instruction::Add(r, v) => format!("{r} = {r}.wrapping_add({});", value(v), r = local(*r)),
+            Instruction::Sub(r, v) => format!("{r} = {r}.wrapping_sub({});", value(v), r = local(*r)),
+            Instruction::Mul(r, v) => format!(
+                "{r} = {r}.wrapping_mul({});\n                muls += 1;",
+                value(v),
+                r = local(*r)
+            ),
+            Instruction::Mod(r, v) => format!(
+                "if {v} == 0 {{\n                    pc = {i};\n                    state.fault = true;\n                    break 'program;\n                }}\n                {r} = {r}.wrapping_rem({v});",
+                v = value(v),
+                i = i,
+                r = local(*r)
+            ),
+            Instruction::Rcv(r) => format!(
+                "match channel.receive({r}) {{\n                    Some(value) => {r} = value,\n                    None => {{\n                        pc = {i};\n                        break 'program;\n                    }}\n                }}",
+                r = local(*r),
+                i = i
+            ),
+            jump => {
+                let (c, offset) = jump.jump().unwrap();
+                let taken = match offset {
+                    Value::Literal(_) => target(jump.jump_target(i).unwrap_or(len)).to_string(),
+                    Value::Register(r) => format!("target({}, {})", i, local(*r)),
+                };
+                falls_through = false;
+                match c {
+                    Condition::Always => format!("pc = {};", taken),
+                    Condition::Never => format!("pc = {};", i + 1),
+                    _ => format!(
+                        "pc = if {} {{ {} }} else {{ {} }};",
+                        condition(c),
+                        taken,
+                        i + 1
+                    ),
+                }
+            }
+        };
+        code.push_str("                ");
+        code.push_str(&line);
+        code.push('\n');
+    }
+    if falls_through {
+        code.push_str(&format!("                pc = {};\n", end));
+    }
+    code
+}
+
+/// Generates a standalone Rust module with a `run` function that executes the
+/// program. `run` returns when the program halts, a `rcv` gets no value from
+/// the channel or a `mod` divides by zero, and can be called again to resume
+/// from there. Arithmetic wraps around on overflow.
+pub fn transpile(program: &[Instruction]) -> String {
+    let registers = registers(program);
+    let has_computed_jumps = program
+        .iter()
+        .any(|i| matches!(i.jump(), Some((_, Value::Register(_)))));
+
+    // A computed jump can go anywhere, and a `rcv` has to be resumable.
+    let mut starts = leaders(program);
+    for (i, instruction) in program.iter().enumerate() {
+        if has_computed_jumps || matches!(instruction, Instruction::Rcv(_)) {
+            starts.insert(i);
+        }
+    }
+    let starts: Vec<usize> = starts.into_iter().collect();
+
+    let names: Vec<String> = registers
+        .iter()
+        .map(|r| format!("{:?}", r.to_string()))
+        .collect();
+    let mut code = format!(
+        "// Generated by `duet transpile`.
+
+pub const LEN: usize = {len};
+pub const REGISTERS: [&str; {count}] = [{names}];
+
+pub trait Channel {{
+    fn send(&mut self, value: i64);
+    /// Called by `rcv` with the current value of the register, returns the
+    /// value to store in it or `None` to stop the program at the `rcv`.
+    fn receive(&mut self, current: i64) -> Option<i64>;
+}}
+
+#[derive(Default)]
+pub struct State {{
+    pub pc: usize,
+    pub registers: [i64; {count}],
+    pub muls: u64,
+    /// Set when a `mod` by zero stopped the program at `pc`.
+    pub fault: bool,
+}}
+
+#[allow(dead_code)]
+fn target(from: usize, offset: i64) -> usize {{
+    match (from as i64).checked_add(offset) {{
+        Some(t) if t >= 0 && t < LEN as i64 => t as usize,
+        _ => LEN,
+    }}
+}}
+
+#[allow(unused_mut, unused_variables, unused_assignments, clippy::all)]
+pub fn run(state: &mut State, channel: &mut impl Channel) {{
+    let mut pc = state.pc;
+    let mut muls = 0;
+",
+        len = program.len(),
+        count = registers.len(),
+        names = names.join(", ")
+    );
+    for (i, r) in registers.iter().enumerate() {
+        code.push_str(&format!(
+            "    let mut {} = state.registers[{}];\n",
+            local(*r),
+            i
+        ));
+    }
+
+    code.push_str("\n    'program: loop {\n        match pc {\n");
+    for (n, &start) in starts.iter().enumerate() {
+        let end = starts.get(n + 1).copied().unwrap_or(program.len());
+        code.push_str(&format!("            {} => {{\n", start));
+        code.push_str(&block(program, start, end));
+        code.push_str("            }\n");
+    }
+    code.push_str("            _ => break 'program,\n        }\n    }\n\n");
+
+    code.push_str("    state.pc = pc;\n");
+    for (i, r) in registers.iter().enumerate() {
+        code.push_str(&format!("    state.registers[{}] = {};\n", i, local(*r)));
+    }
+    code.push_str("    state.muls += muls;\n}\n");
+    code
+}
+
+/// A `main` for the transpiled program that prints what `duet run` prints,
+/// followed by the time the program took.
+pub fn harness(program: &[Instruction]) -> String {
+    let mut code = transpile(program);
+    code.push_str(
+        "
+struct Printer;
+
+impl Channel for Printer {
+    fn send(&mut self, value: i64) {
+        println!(\"snd {}\", value);
+    }
+
+    fn receive(&mut self, _current: i64) -> Option<i64> {
+        None
+    }
+}
+
+fn main() {
+    let mut state = State::default();
+    for arg in std::env::args().skip(1) {
+        let mut parts = arg.splitn(2, '=');
+        let (name, value) = (parts.next().unwrap(), parts.next().unwrap());
+        if let Some(i) = REGISTERS.iter().position(|r| *r == name) {
+            state.registers[i] = value.parse().unwrap();
+        }
+    }
+
+    let start = std::time::Instant::now();
+    run(&mut state, &mut Printer);
+    let elapsed = start.elapsed();
+
+    if state.fault {
+        println!(\"modulo by zero at instruction {}\", state.pc);
+    } else if state.pc < LEN {
+        println!(\"Waiting for a message at instruction {}\", state.pc);
+    }
+    let registers: Vec<String> = REGISTERS
+        .iter()
+        .zip(state.registers.iter())
+        .map(|(r, v)| format!(\"{}={}\", r, v))
+        .collect();
+    println!(\"Registers: {}\", registers.join(\" \"));
+    println!(\"mul: {}\", state.muls);
+    println!(\"elapsed_us {}\", elapsed.as_micros());
+}
+",
+    );
+    code
+}
+
+/// A program compiled by `build`. Its directory is removed when it is
+/// dropped, whether or not the build succeeded.
+pub struct Build {
+    dir: PathBuf,
+    pub binary: PathBuf,
+}
+
+impl Drop for Build {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Compiles the output of `harness` with `rustc` into `dir`.
+pub fn build(source: &str, dir: &Path) -> io::Result<Build> {
+    fs::create_dir_all(dir)?;
+    let build = Build {
+        dir: dir.to_path_buf(),
+        binary: dir.join("program"),
+    };
+    let source_path = dir.join("program.rs");
+    fs::write(&source_path, source)?;
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2018", "-O", "-o"])
+        .arg(&build.binary)
+        .arg(&source_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "rustc failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(build)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process::{self, Command};
+
+    use super::*;
+    use crate::duet::bytecode::{Arithmetic, Event, Machine, Program};
+
+    /// What the harness prints for a program that is given no registers, as
+    /// computed by the interpreter.
+    fn interpret(instructions: &[Instruction]) -> Vec<String> {
+        let program = Program::compile(instructions);
+        let mut machine = Machine::<Number>::with_arithmetic(&program, Arithmetic::Wrapping);
+        let mut lines = Vec::new();
+        loop {
+            match machine.run() {
+                Event::Sent(v) => lines.push(format!("snd {}", v)),
+                Event::Receiving(_) => {
+                    lines.push(format!(
+                        "Waiting for a message at instruction {}",
+                        machine.pc()
+                    ));
+                    break;
+                }
+                Event::Halted => break,
+                Event::Fault(fault) => {
+                    lines.push(format!("{} at instruction {}", fault, machine.pc()));
+                    break;
+                }
+            }
+        }
+        let registers: Vec<String> = registers(instructions)
+            .iter()
+            .map(|&r| format!("{}={}", r, machine.get(r)))
+            .collect();
+        lines.push(format!("Registers: {}", registers.join(" ")));
+        lines.push(format!("mul: {}", machine.muls));
+        lines
+    }
+
+    fn compare(name: &str, source: &str) {
+        let instructions = crate::duet::parse(source).unwrap();
+        let dir = env::temp_dir().join(format!("duet-test-{}-{}", process::id(), name));
+        let build = build(&harness(&instructions), &dir).unwrap();
+        let output = Command::new(&build.binary).output().unwrap();
+        drop(build);
+        assert!(output.status.success());
+        assert!(!dir.exists());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let mut native: Vec<String> = stdout.lines().map(String::from).collect();
+        assert!(native.pop().unwrap().starts_with("elapsed_us "));
+        assert_eq!(native, interpret(&instructions));
+    }
+
+    #[test]
+    fn day18_matches_interpreter() {
+        compare("day18", &fs::read_to_string("data/day18.txt").unwrap());
+    }
+
+    #[test]
+    fn day23_matches_interpreter() {
+        compare("day23", &fs::read_to_string("data/day23.txt").unwrap());
+    }
+
+    #[test]
+    fn extreme_jump_offsets_match_interpreter() {
+        // Only the computed jump is taken, but the others are compiled too.
+        let source = "jnz a 9223372036854775807
+jgz b -9223372036854775808
+set c 1
+set d 9223372036854775807
+jgz c d
+snd c";
+        compare("offsets", source);
+    }
+
+    #[test]
+    fn failed_build_is_cleaned_up() {
+        let dir = env::temp_dir().join(format!("duet-test-{}-invalid", process::id()));
+        assert!(build("fn main() {", &dir).is_err());
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,116 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Wraps the system allocator and keeps track of the number of allocations,
+/// the total number of bytes allocated and the peak heap usage.
+pub struct CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static CURRENT_HEAP: AtomicUsize = AtomicUsize::new(0);
+static PEAK_HEAP: AtomicUsize = AtomicUsize::new(0);
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static MARKS: Mutex<Vec<Stats>> = Mutex::new(Vec::new());
+
+fn record_allocation(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES_ALLOCATED.fetch_add(size, Ordering::Relaxed);
+    let current = CURRENT_HEAP.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_HEAP.fetch_max(current, Ordering::Relaxed);
+}
+
+fn record_deallocation(size: usize) {
+    CURRENT_HEAP.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record_deallocation(layout.size());
+    }
+
+    /// A reallocation counts as a new allocation of the new size.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record_deallocation(layout.size());
+            record_allocation(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Stats {
+    pub allocations: usize,
+    pub bytes_allocated: usize,
+    pub peak_heap: usize,
+}
+
+fn snapshot() -> Stats {
+    Stats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        bytes_allocated: BYTES_ALLOCATED.load(Ordering::Relaxed),
+        peak_heap: PEAK_HEAP.load(Ordering::Relaxed),
+    }
+}
+
+/// Starts measuring a solver run. The marks are preallocated so that taking
+/// them does not show up in the measurements.
+pub fn begin() {
+    let mut marks = MARKS.lock().unwrap();
+    marks.clear();
+    marks.reserve(16);
+    PEAK_HEAP.store(CURRENT_HEAP.load(Ordering::Relaxed), Ordering::Relaxed);
+    marks.push(snapshot());
+    ACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Ends the current part of a solver run.
+pub fn mark_part() {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut marks = MARKS.lock().unwrap();
+    marks.push(snapshot());
+    PEAK_HEAP.store(CURRENT_HEAP.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Stops measuring and returns the statistics of every part, or of the whole
+/// run if the solver never marked the end of a part. The peak heap of a part
+/// is measured relative to the heap in use when the run started.
+pub fn finish() -> Vec<Stats> {
+    ACTIVE.store(false, Ordering::Relaxed);
+    let mut marks = MARKS.lock().unwrap();
+    marks.push(snapshot());
+    let baseline = marks[0];
+    marks
+        .windows(2)
+        .map(|w| Stats {
+            allocations: w[1].allocations - w[0].allocations,
+            bytes_allocated: w[1].bytes_allocated - w[0].bytes_allocated,
+            peak_heap: w[1].peak_heap.saturating_sub(baseline.peak_heap),
+        })
+        .collect()
+}
@@ -0,0 +1,403 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+use std::time::Duration;
+
+use crate::runner::DayResult;
+use crate::tsv::{escape, unescape};
+
+/// Timing changes smaller than this are noise and are left out of the diff.
+const MIN_TIME_CHANGE: Duration = Duration::from_millis(5);
+
+/// One part of one day, which is also what gets saved for the next diff.
+struct Row {
+    day: u32,
+    part: usize,
+    title: String,
+    description: String,
+    value: String,
+    elapsed: Option<Duration>,
+    input_bytes: usize,
+    input_lines: usize,
+}
+
+impl Row {
+    fn to_line(&self) -> String {
+        let elapsed = match self.elapsed {
+            Some(d) => d.as_micros().to_string(),
+            None => String::from("-"),
+        };
+        let fields = [
+            self.day.to_string(),
+            self.part.to_string(),
+            escape(&self.title),
+            escape(&self.description),
+            escape(&self.value),
+            elapsed,
+            self.input_bytes.to_string(),
+            self.input_lines.to_string(),
+        ];
+        fields.join("\t")
+    }
+
+    fn from_line(line: &str) -> Option<Row> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 8 {
+            return None;
+        }
+        Some(Row {
+            day: fields[0].parse().ok()?,
+            part: fields[1].parse().ok()?,
+            title: unescape(fields[2]),
+            description: unescape(fields[3]),
+            value: unescape(fields[4]),
+            elapsed: fields[5].parse().ok().map(Duration::from_micros),
+            input_bytes: fields[6].parse().ok()?,
+            input_lines: fields[7].parse().ok()?,
+        })
+    }
+}
+
+fn rows(results: &[DayResult]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for r in results.iter() {
+        for (i, answer) in r.answers.iter().enumerate() {
+            rows.push(Row {
+                day: r.day,
+                part: i + 1,
+                title: r.title.to_string(),
+                description: answer.description.clone(),
+                value: answer.value.clone(),
+                elapsed: r.elapsed,
+                input_bytes: r.input_bytes,
+                input_lines: r.input_lines,
+            });
+        }
+    }
+    rows
+}
+
+fn load_rows(path: &Path) -> io::Result<Option<Vec<Row>>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents.lines().filter_map(Row::from_line).collect())),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn format_duration(d: Duration) -> String {
+    if d < Duration::from_secs(1) {
+        format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2} s", d.as_secs_f64())
+    }
+}
+
+fn format_elapsed(elapsed: Option<Duration>) -> String {
+    match elapsed {
+        Some(d) => format_duration(d),
+        None => String::from("cached"),
+    }
+}
+
+fn format_input_size(bytes: usize, lines: usize) -> String {
+    format!(
+        "{} bytes, {} line{}",
+        bytes,
+        lines,
+        if lines == 1 { "" } else { "s" }
+    )
+}
+
+fn diff(old: &[Row], new: &[Row]) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    for row in new.iter() {
+        match old.iter().find(|o| o.day == row.day && o.part == row.part) {
+            None => changes.push(format!(
+                "Day {} part {}: new answer `{}`",
+                row.day, row.part, row.value
+            )),
+            Some(o) if o.value != row.value => changes.push(format!(
+                "Day {} part {}: answer changed from `{}` to `{}`",
+                row.day, row.part, o.value, row.value
+            )),
+            _ => {}
+        }
+    }
+
+    for o in old.iter() {
+        if !new.iter().any(|r| r.day == o.day && r.part == o.part) {
+            changes.push(format!("Day {} part {}: no longer answered", o.day, o.part));
+        }
+    }
+
+    for row in new.iter().filter(|r| r.part == 1) {
+        let old_row = old.iter().find(|o| o.day == row.day && o.part == 1);
+        if let (Some(new_time), Some(Some(old_time))) = (row.elapsed, old_row.map(|o| o.elapsed)) {
+            let difference = new_time.abs_diff(old_time);
+            if difference >= MIN_TIME_CHANGE && difference * 4 >= old_time {
+                changes.push(format!(
+                    "Day {}: time changed from {} to {}",
+                    row.day,
+                    format_duration(old_time),
+                    format_duration(new_time)
+                ));
+            }
+        }
+    }
+
+    changes
+}
+
+/// Finds the files in `dir` whose name starts with `dayNN`, as links relative
+/// to the report directory.
+fn visualizations(dir: &Path, output_dir: &Path, day: u32) -> Vec<String> {
+    let prefix = format!("day{:02}", day);
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut links: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+        .map(|e| {
+            let path = e.path();
+            if path.is_absolute() || output_dir.is_absolute() {
+                return path.to_string_lossy().into_owned();
+            }
+            let depth = output_dir
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .count();
+            format!("{}{}", "../".repeat(depth), path.to_string_lossy())
+        })
+        .collect();
+    links.sort();
+    links
+}
+
+fn link_name(link: &str) -> &str {
+    link.rsplit('/').next().unwrap_or(link)
+}
+
+/// Escapes the characters that would end a table cell or start a code span.
+fn markdown_cell(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if matches!(c, '\\' | '`' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// An inline code span in a table cell. The fence is one backtick longer
+/// than any run of backticks in `s`, and pipes are escaped, which GitHub's
+/// tables allow even inside code spans.
+fn markdown_code(s: &str) -> String {
+    let longest_run = s
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    let padding = if s.starts_with('`') || s.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!(
+        "{}{}{}{}{}",
+        fence,
+        padding,
+        s.replace('|', "\\|"),
+        padding,
+        fence
+    )
+}
+
+fn markdown(
+    rows: &[Row],
+    total: Option<Duration>,
+    changes: Option<&[String]>,
+    links: &dyn Fn(u32) -> Vec<String>,
+) -> String {
+    let mut md = String::from("# Advent of Code 2017\n\n");
+    md.push_str("| Day | Puzzle | Part | Description | Answer | Time | Input | Visualizations |\n");
+    md.push_str("|----:|--------|-----:|-------------|--------|-----:|-------|----------------|\n");
+    for row in rows.iter() {
+        let (day, title, time, input, vis) = if row.part == 1 {
+            let vis: Vec<String> = links(row.day)
+                .iter()
+                .map(|l| format!("[{}]({})", link_name(l), l))
+                .collect();
+            (
+                row.day.to_string(),
+                markdown_cell(&row.title),
+                format_elapsed(row.elapsed),
+                format_input_size(row.input_bytes, row.input_lines),
+                vis.join(", "),
+            )
+        } else {
+            Default::default()
+        };
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            day,
+            title,
+            row.part,
+            markdown_cell(&row.description),
+            markdown_code(&row.value),
+            time,
+            input,
+            vis
+        ));
+    }
+
+    if let Some(total) = total {
+        md.push_str(&format!("\nTotal time: {}\n", format_duration(total)));
+    }
+
+    md.push_str("\n## Changes since the previous report\n\n");
+    match changes {
+        None => md.push_str("There is no previous report to compare against.\n"),
+        Some([]) => md.push_str("No changes.\n"),
+        Some(changes) => {
+            for c in changes.iter() {
+                md.push_str(&format!("- {}\n", c));
+            }
+        }
+    }
+    md
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders inline code spans written as `...` in the change descriptions.
+fn html_code_spans(s: &str) -> String {
+    let mut result = String::new();
+    for (i, part) in html_escape(s).split('`').enumerate() {
+        if i % 2 == 1 {
+            result.push_str(&format!("<code>{}</code>", part));
+        } else {
+            result.push_str(part);
+        }
+    }
+    result
+}
+
+fn html(
+    rows: &[Row],
+    total: Option<Duration>,
+    changes: Option<&[String]>,
+    links: &dyn Fn(u32) -> Vec<String>,
+) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Advent of Code 2017</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+td.number { text-align: right; }
+tr.first td { border-top: 2px solid #888; }
+</style>
+</head>
+<body>
+<h1>Advent of Code 2017</h1>
+<table>
+<tr><th>Day</th><th>Puzzle</th><th>Part</th><th>Description</th><th>Answer</th><th>Time</th><th>Input</th><th>Visualizations</th></tr>
+",
+    );
+    for row in rows.iter() {
+        if row.part == 1 {
+            let vis: Vec<String> = links(row.day)
+                .iter()
+                .map(|l| {
+                    format!(
+                        "<a href=\"{}\">{}</a>",
+                        html_escape(l),
+                        html_escape(link_name(l))
+                    )
+                })
+                .collect();
+            html.push_str(&format!(
+                "<tr class=\"first\"><td class=\"number\">{}</td><td>{}</td><td class=\"number\">1</td><td>{}</td><td><code>{}</code></td><td class=\"number\">{}</td><td>{}</td><td>{}</td></tr>\n",
+                row.day,
+                html_escape(&row.title),
+                html_escape(&row.description),
+                html_escape(&row.value),
+                format_elapsed(row.elapsed),
+                format_input_size(row.input_bytes, row.input_lines),
+                vis.join(", ")
+            ));
+        } else {
+            html.push_str(&format!(
+                "<tr><td></td><td></td><td class=\"number\">{}</td><td>{}</td><td><code>{}</code></td><td></td><td></td><td></td></tr>\n",
+                row.part,
+                html_escape(&row.description),
+                html_escape(&row.value)
+            ));
+        }
+    }
+    html.push_str("</table>\n");
+
+    if let Some(total) = total {
+        html.push_str(&format!("<p>Total time: {}</p>\n", format_duration(total)));
+    }
+
+    html.push_str("<h2>Changes since the previous report</h2>\n");
+    match changes {
+        None => html.push_str("<p>There is no previous report to compare against.</p>\n"),
+        Some([]) => html.push_str("<p>No changes.</p>\n"),
+        Some(changes) => {
+            html.push_str("<ul>\n");
+            for c in changes.iter() {
+                html.push_str(&format!("<li>{}</li>\n", html_code_spans(c)));
+            }
+            html.push_str("</ul>\n");
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Writes `report.md` and `report.html` to `output_dir`, comparing against
+/// the results saved there by the previous report, and then saves the new
+/// results for the next comparison.
+pub fn write(results: &[DayResult], output_dir: &Path, visualization_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(output_dir)?;
+    let results_path = output_dir.join("results.tsv");
+
+    let rows = rows(results);
+    let changes = load_rows(&results_path)?.map(|old| diff(&old, &rows));
+    let total = results.iter().map(|r| r.elapsed).sum::<Option<Duration>>();
+    let links = |day| visualizations(visualization_dir, output_dir, day);
+
+    fs::write(
+        output_dir.join("report.md"),
+        markdown(&rows, total, changes.as_deref(), &links),
+    )?;
+    fs::write(
+        output_dir.join("report.html"),
+        html(&rows, total, changes.as_deref(), &links),
+    )?;
+
+    let mut saved = String::new();
+    for row in rows.iter() {
+        saved.push_str(&row.to_line());
+        saved.push('\n');
+    }
+    fs::write(results_path, saved)
+}
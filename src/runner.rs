@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use crate::cache::Cache;
+use crate::solver::{Answer, Parameters, Solver};
+
+/// Everything the runner knows about one run of a solver.
+pub struct DayResult {
+    pub day: u32,
+    pub title: &'static str,
+    pub answers: Vec<Answer>,
+    /// `None` if the answers came from the cache.
+    pub elapsed: Option<Duration>,
+    pub input_bytes: usize,
+    pub input_lines: usize,
+}
+
+/// Runs a solver, consulting and updating the cache if one is given.
+pub fn run(
+    solver: &'static Solver,
+    input: &str,
+    params: &Parameters,
+    cache: Option<&mut Cache>,
+) -> DayResult {
+    let mut result = DayResult {
+        day: solver.day,
+        title: solver.title,
+        answers: Vec::new(),
+        elapsed: None,
+        input_bytes: input.len(),
+        input_lines: input.lines().count(),
+    };
+
+    if let Some(cache) = cache {
+        match cache.lookup(solver, input, params) {
+            Some(answers) => result.answers = answers,
+            None => {
+                run_uncached(solver, input, params, &mut result);
+                cache.insert(solver, input, params, &result.answers);
+            }
+        }
+    } else {
+        run_uncached(solver, input, params, &mut result);
+    }
+
+    result
+}
+
+fn run_uncached(solver: &Solver, input: &str, params: &Parameters, result: &mut DayResult) {
+    let start = Instant::now();
+    result.answers = (solver.solve)(input, params);
+    result.elapsed = Some(start.elapsed());
+}
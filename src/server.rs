@@ -0,0 +1,342 @@
+//! A local HTTP API for the solvers of the registry in `solver`.
+//!
+//! The server looks solvers and their parameters up in the registry itself,
+//! but runs every solver in a child `aoc solve` process. A solver cannot be
+//! stopped from another thread, so running it in-process would leave it
+//! running past its timeout, and an input that makes it overflow its stack
+//! or run out of memory would take the whole server down.
+
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::batch::{self, Outcome};
+use crate::solver::{self, Answer, Solver};
+
+const MAX_BODY_SIZE: usize = 1 << 20;
+
+/// The most bytes read for the request line and the headers together.
+const MAX_HEAD_SIZE: u64 = 16 << 10;
+
+/// The most connections handled at once, each with its own thread and
+/// maybe a solver process. More are turned away.
+const MAX_CONNECTIONS: usize = 32;
+
+#[derive(Clone)]
+pub struct Config {
+    pub address: String,
+    pub timeout: Duration,
+    /// The `aoc` executable, which runs every solver in a child process.
+    pub exe: PathBuf,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Response {
+        Response { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(status, format!("{{\"error\":{}}}", json_string(message)))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            422 => "Unprocessable Entity",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn write_to(&self, stream: &mut TcpStream) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(i) => (pair[..i].to_string(), pair[i + 1..].to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Reads a line of the request head, of which `remaining` bytes are left.
+fn read_head_line<R: BufRead>(reader: &mut R, remaining: &mut u64) -> Result<String, Response> {
+    let mut line = String::new();
+    let read = reader
+        .take(*remaining)
+        .read_line(&mut line)
+        .map_err(|_| Response::error(400, "malformed request"))?;
+    *remaining -= read as u64;
+    if !line.ends_with('\n') && *remaining == 0 {
+        return Err(Response::error(431, "request line or headers too large"));
+    }
+    Ok(line)
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, Response> {
+    let bad_request = |_| Response::error(400, "malformed request");
+    let mut reader = BufReader::new(stream);
+    let mut remaining = MAX_HEAD_SIZE;
+
+    let request_line = read_head_line(&mut reader, &mut remaining)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target),
+        _ => return Err(Response::error(400, "malformed request line")),
+    };
+    let (path, query) = match target.find('?') {
+        Some(i) => (target[..i].to_string(), parse_query(&target[i + 1..])),
+        None => (target.to_string(), Vec::new()),
+    };
+
+    let mut content_length = 0;
+    loop {
+        let header = read_head_line(&mut reader, &mut remaining)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(i) = header.find(':') {
+            if header[..i].eq_ignore_ascii_case("content-length") {
+                content_length = header[i + 1..]
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(Response::error(413, "request body too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(bad_request)?;
+
+    Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    })
+}
+
+fn answers_json(day: u32, answers: &[Answer], elapsed: Duration) -> String {
+    let answers: Vec<String> = answers
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            format!(
+                "{{\"part\":{},\"description\":{},\"value\":{}}}",
+                i + 1,
+                json_string(&a.description),
+                json_string(&a.value)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"day\":{},\"answers\":[{}],\"elapsed_ms\":{}}}",
+        day,
+        answers.join(","),
+        elapsed.as_millis()
+    )
+}
+
+fn days_json() -> String {
+    let days: Vec<String> = solver::solvers()
+        .iter()
+        .map(|s| {
+            let parameters: Vec<String> = s
+                .parameters
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{{\"name\":{},\"default\":{},\"description\":{}}}",
+                        json_string(p.name),
+                        json_string(p.default),
+                        json_string(p.description)
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"day\":{},\"title\":{},\"parameters\":[{}]}}",
+                s.day,
+                json_string(s.title),
+                parameters.join(",")
+            )
+        })
+        .collect();
+    format!("{{\"days\":[{}]}}", days.join(","))
+}
+
+/// Runs the solver in a child process, so that a panic caused by bad input
+/// is reported instead of taking down the connection, and a solver that takes
+/// too long is killed rather than left running.
+fn run_solver(
+    solver: &'static Solver,
+    input: Vec<u8>,
+    params: &[(String, String)],
+    config: &Config,
+) -> Response {
+    match batch::run_isolated(&config.exe, solver.day, params, input, config.timeout) {
+        Ok(Outcome::Solved { answers, elapsed }) => {
+            Response::json(200, answers_json(solver.day, &answers, elapsed))
+        }
+        Ok(Outcome::Failed(message)) => Response::error(
+            422,
+            &format!("solver failed on the given input: {}", message),
+        ),
+        Ok(Outcome::TimedOut) => Response::error(504, "solver timed out"),
+        Err(e) => Response::error(500, &format!("cannot run the solver: {}", e)),
+    }
+}
+
+fn handle_solve(request: Request, day: &str, config: &Config) -> Response {
+    let solver = match day.parse().ok().and_then(solver::find) {
+        Some(s) => s,
+        None => return Response::error(404, "no solver for this day"),
+    };
+
+    let mut params = solver.default_parameters();
+    for (name, value) in request.query.iter() {
+        if let Err(e) = params.set(name, value) {
+            return Response::error(400, &e);
+        }
+    }
+
+    if std::str::from_utf8(&request.body).is_err() {
+        return Response::error(400, "puzzle input must be valid UTF-8");
+    }
+    run_solver(solver, request.body, &request.query, config)
+}
+
+fn route(request: Request, config: &Config) -> Response {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["days"]) => Response::json(200, days_json()),
+        ("POST", ["2017", "day", day]) => {
+            let day = day.to_string();
+            handle_solve(request, &day, config)
+        }
+        (_, ["days"]) | (_, ["2017", "day", _]) => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, config: &Config) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let response = match read_request(&stream) {
+        Ok(request) => route(request, config),
+        Err(response) => response,
+    };
+    response.write_to(&mut stream)
+}
+
+/// One of the connections counted towards `MAX_CONNECTIONS`, until dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn take(active: &Arc<AtomicUsize>) -> Option<Slot> {
+        if active.fetch_add(1, Ordering::SeqCst) < MAX_CONNECTIONS {
+            Some(Slot(Arc::clone(active)))
+        } else {
+            active.fetch_sub(1, Ordering::SeqCst);
+            None
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn serve(config: &Config) -> io::Result<()> {
+    let listener = TcpListener::bind(&config.address)?;
+    println!("Listening on http://{}", listener.local_addr()?);
+
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let slot = match Slot::take(&active) {
+            Some(slot) => slot,
+            None => {
+                let response = Response::error(503, "too many connections");
+                if let Err(e) = response.write_to(&mut stream) {
+                    eprintln!("Failed to handle request: {}", e);
+                }
+                continue;
+            }
+        };
+        let config = config.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &config) {
+                eprintln!("Failed to handle request: {}", e);
+            }
+            drop(slot);
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::prelude::*;
+use std::str::FromStr;
+
+use crate::*;
+
+/// One line of a solver's output, e.g. the answer to one part of a puzzle.
+pub struct Answer {
+    pub description: String,
+    pub value: String,
+}
+
+impl Answer {
+    pub fn new<T: ToString>(description: &str, value: T) -> Answer {
+        Answer {
+            description: description.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+/// Called by a solver once it is done with the first part of the puzzle, so
+/// that `aoc profile` can tell the parts apart. Solvers that work out both
+/// parts in the same pass do not call it, and are profiled as a whole.
+pub fn end_part() {
+    #[cfg(feature = "profile")]
+    crate::profile::mark_part();
+}
+
+/// A constant that used to be hardcoded in a solver and can now be overridden.
+pub struct Parameter {
+    pub name: &'static str,
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+#[derive(Clone)]
+pub struct Parameters {
+    values: HashMap<&'static str, String>,
+}
+
+impl Parameters {
+    pub fn new(declared: &'static [Parameter]) -> Parameters {
+        let mut values = HashMap::new();
+        for p in declared.iter() {
+            values.insert(p.name, p.default.to_string());
+        }
+        Parameters { values }
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match self.values.get_mut(name) {
+            Some(v) => {
+                *v = value.to_string();
+                Ok(())
+            }
+            None => Err(format!("unknown parameter: {}", name)),
+        }
+    }
+
+    /// Panics if the value cannot be parsed, like any other malformed input.
+    pub fn get<T: FromStr>(&self, name: &str) -> T {
+        match self.values.get(name).map(|v| v.parse()) {
+            Some(Ok(v)) => v,
+            _ => panic!("invalid value for parameter {}", name),
+        }
+    }
+
+    /// All parameters with their current values, sorted by name.
+    pub fn to_vec(&self) -> Vec<(&str, &str)> {
+        let mut v: Vec<(&str, &str)> = self.values.iter().map(|(n, v)| (*n, v.as_str())).collect();
+        v.sort();
+        v
+    }
+}
+
+pub struct Solver {
+    pub day: u32,
+    pub title: &'static str,
+    /// Bump whenever a change to the solver could change its answers, so that
+    /// cached answers computed by an older version are no longer used.
+    pub version: u32,
+    pub parameters: &'static [Parameter],
+    pub solve: fn(&str, &Parameters) -> Vec<Answer>,
+}
+
+impl Solver {
+    pub fn default_parameters(&self) -> Parameters {
+        Parameters::new(self.parameters)
+    }
+
+    pub fn input_path(&self) -> String {
+        format!("data/day{:02}.txt", self.day)
+    }
+}
+
+macro_rules! solver {
+    ($day:expr, $module:ident, $title:expr, $version:expr) => {
+        solver!($day, $module, $title, $version, &[])
+    };
+    ($day:expr, $module:ident, $title:expr, $version:expr, $parameters:expr) => {
+        Solver {
+            day: $day,
+            title: $title,
+            version: $version,
+            parameters: $parameters,
+            solve: $module::solve,
+        }
+    };
+}
+
+static SOLVERS: [Solver; 25] = [
+    solver!(1, day01, "Inverse Captcha", 1),
+    solver!(2, day02, "Corruption Checksum", 1),
+    solver!(3, day03, "Spiral Memory", 1),
+    solver!(4, day04, "High-Entropy Passphrases", 1),
+    solver!(5, day05, "A Maze of Twisty Trampolines, All Alike", 1),
+    solver!(6, day06, "Memory Reallocation", 1),
+    solver!(7, day07, "Recursive Circus", 1),
+    solver!(8, day08, "I Heard You Like Registers", 1),
+    solver!(9, day09, "Stream Processing", 1),
+    solver!(10, day10, "Knot Hash", 1),
+    solver!(11, day11, "Hex Ed", 1),
+    solver!(12, day12, "Digital Plumber", 1),
+    solver!(13, day13, "Packet Scanners", 1),
+    solver!(14, day14, "Disk Defragmentation", 1),
+    solver!(15, day15, "Dueling Generators", 1, day15::PARAMETERS),
+    solver!(16, day16, "Permutation Promenade", 1, day16::PARAMETERS),
+    solver!(17, day17, "Spinlock", 1, day17::PARAMETERS),
+    solver!(18, day18, "Duet", 2),
+    solver!(19, day19, "A Series of Tubes", 1),
+    solver!(20, day20, "Particle Swarm", 1),
+    solver!(21, day21, "Fractal Art", 2, day21::PARAMETERS),
+    solver!(22, day22, "Sporifica Virus", 2, day22::PARAMETERS),
+    solver!(23, day23, "Coprocessor Conflagration", 2, day23::PARAMETERS),
+    solver!(24, day24, "Electromagnetic Moat", 1),
+    solver!(25, day25, "The Halting Problem", 2, day25::PARAMETERS),
+];
+
+pub fn solvers() -> &'static [Solver] {
+    &SOLVERS
+}
+
+pub fn find(day: u32) -> Option<&'static Solver> {
+    SOLVERS.iter().find(|s| s.day == day)
+}
+
+/// Entry point of the `dayNN` binaries: solve the input on stdin with the
+/// default parameters.
+pub fn main(day: u32) -> io::Result<()> {
+    let mut input = String::new();
+    io::stdin().lock().read_to_string(&mut input).unwrap();
+
+    let solver = find(day).unwrap();
+    for answer in (solver.solve)(&input, &solver.default_parameters()) {
+        println!("{}: {}", answer.description, answer.value);
+    }
+
+    Ok(())
+}